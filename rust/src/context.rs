@@ -1,32 +1,60 @@
+use crate::error::ParseError;
 use crate::guard::Guard;
+use crate::options::ParseOptions;
+use crate::span::LineIndex;
+use crate::utils::Normalized;
 
 #[derive(Clone)]
-pub struct Context {
+pub struct Context<'a> {
     pub indent: usize,
+    pub options: &'a ParseOptions,
+    /// Skip malformed entries instead of failing, see `errors`.
+    pub recover: bool,
+    /// Errors met while recovering.
+    pub errors: Vec<ParseError>,
+    /// The input the parsed text was normalized from, if any.
+    pub normalized: Option<&'a Normalized<'a>>,
 }
 
-impl Context {
-    pub fn new(indent: usize) -> Context {
-        Context { indent }
+impl<'a> Context<'a> {
+    pub fn new(indent: usize, options: &'a ParseOptions) -> Context<'a> {
+        Context {
+            indent,
+            options,
+            recover: false,
+            errors: vec![],
+            normalized: None,
+        }
     }
 
-    pub fn guard(&mut self, new_ctx: Context) -> ContextGuard {
+    /// Index of the parsed text `input`, giving positions in the original
+    /// input when it was normalized.
+    pub fn line_index(&self, input: &'a str) -> LineIndex<'a> {
+        match self.normalized {
+            Some(normalized) => normalized.index(),
+            None => LineIndex::new(input),
+        }
+    }
+
+    pub fn guard(&mut self, new_ctx: Context<'a>) -> ContextGuard {
         ContextGuard::new(self, new_ctx)
     }
 }
 
 pub struct ContextGuard {
-    ctx: Context,
+    indent: usize,
 }
 
-impl Guard<Context> for ContextGuard {
-    fn new(ctx: &mut Context, new_ctx: Context) -> ContextGuard {
-        let original_ctx = ctx.clone();
+impl<'a> Guard<Context<'a>> for ContextGuard {
+    fn new(ctx: &mut Context<'a>, new_ctx: Context<'a>) -> ContextGuard {
+        let original_indent = ctx.indent;
         ctx.indent = new_ctx.indent;
-        ContextGuard { ctx: original_ctx }
+        ContextGuard {
+            indent: original_indent,
+        }
     }
 
     fn restore(&self, ctx: &mut Context) {
-        ctx.indent = self.ctx.indent;
+        ctx.indent = self.indent;
     }
 }
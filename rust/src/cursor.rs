@@ -1,6 +1,6 @@
 use crate::error::ParseError;
 use crate::guard::Guard;
-use crate::indent::is_indent_char;
+use crate::indent::{calc_indent_size, indent_offset, is_indent_char};
 
 pub struct Cursor<'a> {
     pub pos: usize,
@@ -8,7 +8,7 @@ pub struct Cursor<'a> {
 }
 
 impl<'a> Cursor<'a> {
-    pub fn new(input: &'a str) -> Cursor {
+    pub fn new(input: &'a str) -> Cursor<'a> {
         Cursor { pos: 0, input }
     }
 
@@ -112,16 +112,16 @@ impl<'a> Cursor<'a> {
         Some(&self.input[start..start + end])
     }
 
-    pub fn take_until_dedent(&mut self, indent: usize) -> Vec<&str> {
+    pub fn take_until_dedent(&mut self, indent: usize, tab_size: usize) -> Vec<&str> {
         let mut lines = vec![];
         let mut process_line = |start: usize, end: usize| {
             let line = &self.input[start..end];
-            let indent_size = line.chars().take_while(|c| is_indent_char(*c)).count();
+            let indent_size = calc_indent_size(line, tab_size);
 
             if line.chars().all(is_indent_char) {
                 lines.push("");
             } else if indent_size > indent {
-                lines.push(&line[indent..]);
+                lines.push(&line[indent_offset(line, indent, tab_size)..]);
             } else {
                 return None;
             }
@@ -167,13 +167,17 @@ impl<'a> Cursor<'a> {
     }
 
     pub fn eat_empty_lines(&mut self) {
-        while let Some(line_ending) = self.input[self.pos..].find('\n') {
-            let line = &self.input[self.pos..self.pos + line_ending];
-            if line.chars().all(|c| c.is_whitespace()) {
-                self.pos += line_ending + 1;
-            } else {
+        while let Some(line_ending) = self.input[self.pos..].find(['\n', '\r']) {
+            let rest = &self.input[self.pos..];
+            if !rest[..line_ending].chars().all(|c| c.is_whitespace()) {
                 break;
             }
+            let line_break = if rest[line_ending..].starts_with("\r\n") {
+                2
+            } else {
+                1
+            };
+            self.pos += line_ending + line_break;
         }
     }
 
@@ -192,14 +196,14 @@ impl<'a> Cursor<'a> {
             self.pos += s.len();
             Ok(())
         } else {
-            Err(ParseError::invalid_value(
-                self.pos,
-                format!("Expected {}", s),
-            ))
+            match self.peek() {
+                Some(c) => Err(ParseError::unexpected_token(self.pos, &[s], c)),
+                None => Err(ParseError::unexpected_end(self.pos, &[s])),
+            }
         }
     }
 
-    pub fn guard<'b>(&'b mut self) -> CursorGuard {
+    pub fn guard(&mut self) -> CursorGuard {
         CursorGuard::new(
             self,
             Cursor {
@@ -265,7 +269,7 @@ many summary in github.
         let doc = r#"    This is a test string
         Next line"#;
         let mut cursor = Cursor::new(doc);
-        let lines = cursor.take_until_dedent(initial_indent);
+        let lines = cursor.take_until_dedent(initial_indent, 4);
         assert_eq!(lines, Vec::<&str>::new());
     }
 
@@ -276,7 +280,7 @@ many summary in github.
         Next line"#;
         let mut cursor = Cursor::new(doc);
         // cursor.eat_indent();
-        let lines = cursor.take_until_dedent(initial_indent);
+        let lines = cursor.take_until_dedent(initial_indent, 4);
         assert_eq!(lines, vec![" This is a test string", "    Next line"]);
     }
 
@@ -287,7 +291,7 @@ many summary in github.
         Next line
     dedent line"#;
         let mut cursor = Cursor::new(doc);
-        let lines = cursor.take_until_dedent(initial_indent);
+        let lines = cursor.take_until_dedent(initial_indent, 4);
         assert_eq!(lines, vec!["    This is a test string", "    Next line"]);
         let remaining = cursor.take_remaining();
         assert_eq!(remaining, "    dedent line")
@@ -0,0 +1,364 @@
+//! Problems found while parsing and their rendering.
+
+use crate::error::ParseError;
+use crate::span::{LineIndex, Span};
+use crate::utils::line_ranges;
+
+/// A secondary message attached to a location of the input.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Label {
+    /// The part of the input the label points at.
+    pub span: Span,
+    /// Printed after the carets.
+    pub message: String,
+}
+
+/// How bad a [`Diagnostic`] is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Severity {
+    /// The input is malformed, part of it was skipped.
+    #[default]
+    Error,
+    /// The input was parsed but is likely not what was meant.
+    Warning,
+}
+
+impl Severity {
+    /// `"error"` or `"warning"`, as rendered.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A problem found while parsing, located in the input.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    /// Whether the problem is an error or a warning.
+    pub severity: Severity,
+    /// Stable error code, see [`crate::error::ErrorCode`].
+    pub code: Option<&'static str>,
+    /// The headline, e.g. "expected ':', found 'D'".
+    pub message: String,
+    /// Where the problem is.
+    pub span: Span,
+    /// Other locations worth pointing at.
+    pub labels: Vec<Label>,
+    /// Extra context printed below the source, e.g. the enclosing section.
+    pub notes: Vec<String>,
+    /// A suggestion on how to fix the problem.
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// A diagnostic with only a message and a location.
+    pub fn new(message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            message,
+            span,
+            labels: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    /// Locate a parse error, its context becomes a note.
+    pub fn from_error(err: &ParseError, index: &LineIndex) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(err.message(), index.span(err.pos(), err.pos()));
+        diagnostic.code = Some(err.code().as_str());
+        match err.context_message() {
+            Some(context) => diagnostic.with_note(context),
+            None => diagnostic,
+        }
+    }
+
+    /// A warning about indentation mixing tabs and spaces, `span` covering
+    /// the indentation.
+    pub fn inconsistent_indent(span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            code: Some("W001"),
+            help: Some("indent with either tabs or spaces".to_string()),
+            ..Diagnostic::new(
+                "inconsistent use of tabs and spaces in indentation".to_string(),
+                span,
+            )
+        }
+    }
+
+    /// Append a note.
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+}
+
+/// Options of [`render_diagnostic`].
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    /// Highlight the output with ANSI escape codes.
+    pub color: bool,
+}
+
+struct Style {
+    error: &'static str,
+    warning: &'static str,
+    gutter: &'static str,
+    label: &'static str,
+    reset: &'static str,
+}
+
+impl Style {
+    fn new(color: bool) -> Style {
+        if color {
+            Style {
+                error: "\x1b[1;31m",
+                warning: "\x1b[1;33m",
+                gutter: "\x1b[1;34m",
+                label: "\x1b[1;36m",
+                reset: "\x1b[0m",
+            }
+        } else {
+            Style {
+                error: "",
+                warning: "",
+                gutter: "",
+                label: "",
+                reset: "",
+            }
+        }
+    }
+}
+
+/// Carets under the part of `line` covered by `span`, starting at `span`'s column.
+fn underline(line: &str, span: &Span) -> (usize, usize) {
+    let line_len = line.chars().count();
+    let start = span.start_pos.col.min(line_len);
+    let end = if span.end_pos.line == span.start_pos.line {
+        span.end_pos.col
+    } else {
+        line_len
+    };
+    (start, end.saturating_sub(start).max(1))
+}
+
+/// Render a diagnostic with the source lines it points at, in the style of rustc.
+///
+/// Columns are 1-based in the rendered text.
+///
+/// ```text
+/// error[E003]: expected ':', found 'D'
+///  --> 2:16
+///   |
+/// 2 |     arg1 (int) Description of arg1
+///   |               ^
+/// ```
+pub fn render_diagnostic(input: &str, diagnostic: &Diagnostic, options: &RenderOptions) -> String {
+    let style = Style::new(options.color);
+    let lines: Vec<&str> = line_ranges(input)
+        .into_iter()
+        .map(|(start, end)| &input[start..end])
+        .collect();
+    let color = match diagnostic.severity {
+        Severity::Error => style.error,
+        Severity::Warning => style.warning,
+    };
+    let mut marks: Vec<(&Span, &str, &str)> = vec![(&diagnostic.span, "", color)];
+    marks.extend(
+        diagnostic
+            .labels
+            .iter()
+            .map(|label| (&label.span, label.message.as_str(), style.label)),
+    );
+    marks.sort_by_key(|(span, _, _)| span.start);
+    let gutter_width = marks
+        .iter()
+        .map(|(span, _, _)| span.start_pos.line.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let pad = " ".repeat(gutter_width);
+
+    let code = diagnostic
+        .code
+        .map_or(String::new(), |code| format!("[{}]", code));
+    let mut res = format!(
+        "{}{}{}{}: {}\n",
+        color,
+        diagnostic.severity.as_str(),
+        code,
+        style.reset,
+        diagnostic.message
+    );
+    res.push_str(&format!(
+        "{}{}-->{} {}:{}\n",
+        pad,
+        style.gutter,
+        style.reset,
+        diagnostic.span.start_pos.line,
+        diagnostic.span.start_pos.col + 1
+    ));
+    res.push_str(&format!("{} {}|{}\n", pad, style.gutter, style.reset));
+    let mut last_line = None;
+    for (span, message, color) in marks {
+        let lineno = span.start_pos.line;
+        let line = lines.get(lineno - 1).copied().unwrap_or("");
+        if last_line != Some(lineno) {
+            if last_line.is_some_and(|last| lineno > last + 1) {
+                res.push_str(&format!("{}...{}\n", style.gutter, style.reset));
+            }
+            res.push_str(&format!(
+                "{}{:>width$} |{} {}\n",
+                style.gutter,
+                lineno,
+                style.reset,
+                line,
+                width = gutter_width
+            ));
+            last_line = Some(lineno);
+        }
+        let (start, len) = underline(line, span);
+        let mut mark_line = format!(
+            "{} {}|{} {}{}{}",
+            pad,
+            style.gutter,
+            style.reset,
+            " ".repeat(start),
+            color,
+            "^".repeat(len)
+        );
+        if !message.is_empty() {
+            mark_line.push(' ');
+            mark_line.push_str(message);
+        }
+        mark_line.push_str(style.reset);
+        res.push_str(mark_line.trim_end());
+        res.push('\n');
+    }
+    for note in &diagnostic.notes {
+        res.push_str(&format!(
+            "{} {}={} note: {}\n",
+            pad, style.gutter, style.reset, note
+        ));
+    }
+    if let Some(help) = &diagnostic.help {
+        res.push_str(&format!(
+            "{} {}={} help: {}\n",
+            pad, style.gutter, style.reset, help
+        ));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_diagnostic() {
+        let input = "Args:\n    arg1 (int) Description of arg1\n";
+        let index = LineIndex::new(input);
+        let mut diagnostic = Diagnostic::new("Expected :".to_string(), index.span(21, 21))
+            .with_note("entries look like `name (type): description`".to_string());
+        diagnostic.labels.push(Label {
+            span: index.span(16, 19),
+            message: "type of `arg1`".to_string(),
+        });
+        diagnostic.help = Some("add `:` after the type".to_string());
+        assert_eq!(
+            render_diagnostic(input, &diagnostic, &RenderOptions::default()),
+            "error: Expected :
+ --> 2:16
+  |
+2 |     arg1 (int) Description of arg1
+  |           ^^^ type of `arg1`
+  |                ^
+  = note: entries look like `name (type): description`
+  = help: add `:` after the type
+"
+        );
+    }
+
+    #[test]
+    fn test_render_diagnostic_first_column() {
+        let input = "Args:\n:\n";
+        let index = LineIndex::new(input);
+        let diagnostic =
+            Diagnostic::new("Invalid identifier start: :".to_string(), index.span(6, 6));
+        assert_eq!(
+            render_diagnostic(input, &diagnostic, &RenderOptions::default()),
+            "error: Invalid identifier start: :\n --> 2:1\n  |\n2 | :\n  | ^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_diagnostic_multi_line() {
+        let input = "Summary.\n\n\nArgs:\n    arg1 (int) Description of arg1\n";
+        let index = LineIndex::new(input);
+        let mut diagnostic = Diagnostic::new("Expected :".to_string(), index.span(32, 32));
+        diagnostic.labels.push(Label {
+            span: index.span(0, 8),
+            message: "summary".to_string(),
+        });
+        assert_eq!(
+            render_diagnostic(input, &diagnostic, &RenderOptions::default()),
+            "error: Expected :
+ --> 5:16
+  |
+1 | Summary.
+  | ^^^^^^^^ summary
+...
+5 |     arg1 (int) Description of arg1
+  |                ^
+"
+        );
+    }
+
+    #[test]
+    fn test_render_diagnostic_color() {
+        let input = "x";
+        let index = LineIndex::new(input);
+        let diagnostic = Diagnostic::new("Oops".to_string(), index.span(0, 1));
+        let rendered = render_diagnostic(input, &diagnostic, &RenderOptions { color: true });
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m: Oops\n"));
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+
+    #[test]
+    fn test_from_error() {
+        let input = "Args:\n    arg1 (int) Description of arg1\n";
+        let err = ParseError::unexpected_token(21, &[":"], 'D')
+            .with_context("argument `arg1`".to_string())
+            .with_context("section `Args`".to_string());
+        let diagnostic = Diagnostic::from_error(&err, &LineIndex::new(input));
+        assert_eq!(
+            render_diagnostic(input, &diagnostic, &RenderOptions::default()),
+            "error[E003]: expected ':', found 'D'
+ --> 2:16
+  |
+2 |     arg1 (int) Description of arg1
+  |                ^
+  = note: in argument `arg1` of section `Args`
+"
+        );
+    }
+
+    #[test]
+    fn test_render_warning() {
+        let input = "Args:\n    x: The x.\n  \ty: The y.\n";
+        let diagnostic = Diagnostic::inconsistent_indent(LineIndex::new(input).span(20, 23));
+        assert_eq!(
+            render_diagnostic(input, &diagnostic, &RenderOptions::default()),
+            "warning[W001]: inconsistent use of tabs and spaces in indentation
+ --> 3:1
+  |
+3 |   \ty: The y.
+  | ^^^
+  = help: indent with either tabs or spaces
+"
+        );
+    }
+}
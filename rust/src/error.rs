@@ -1,71 +1,271 @@
-#[derive(PartialEq, Debug)]
+//! The error returned when parsing fails.
+
+use crate::diagnostic::{render_diagnostic, Diagnostic, RenderOptions};
+use crate::span::LineIndex;
+
+/// Stable identifiers of the parse errors, safe to match on in tools.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorCode {
+    /// `E001`, the input ended in the middle of a construct.
+    UnexpectedEnd,
+    /// `E002`, an argument name does not start like an identifier.
+    InvalidIdentifier,
+    /// `E003`, a token other than the expected ones was found.
+    UnexpectedToken,
+    /// `E004`, the input is longer than [`crate::ParseOptions::max_input_size`].
+    InputTooLarge,
+}
+
+impl ErrorCode {
+    /// The code as shown in diagnostics, e.g. `E003`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedEnd => "E001",
+            ErrorCode::InvalidIdentifier => "E002",
+            ErrorCode::UnexpectedToken => "E003",
+            ErrorCode::InputTooLarge => "E004",
+        }
+    }
+}
+
+/// Why and where parsing failed.
+#[derive(PartialEq, Debug, Clone)]
 pub enum ParseError {
+    /// The input ended too early.
     UnexpectedEnd(UnexpectedEnd),
+    /// Something else than what was expected was found.
     InvalidValue(InvalidValue),
 }
 
-#[derive(PartialEq, Debug)]
-struct UnexpectedEnd {
+/// Details of [`ParseError::UnexpectedEnd`], see the accessors of [`ParseError`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnexpectedEnd {
     pos: usize,
+    expected: Vec<String>,
+    context: Vec<String>,
 }
 
-#[derive(PartialEq, Debug)]
-struct InvalidValue {
+/// Details of [`ParseError::InvalidValue`], see the accessors of [`ParseError`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct InvalidValue {
     pos: usize,
-    message: String,
+    code: ErrorCode,
+    expected: Vec<String>,
+    found: String,
+    context: Vec<String>,
+}
+
+/// Quote punctuation tokens, named token classes like `newline` are kept as is.
+fn describe(token: &str) -> String {
+    if token.chars().any(|c| c.is_alphanumeric()) {
+        token.to_string()
+    } else {
+        format!("'{}'", token)
+    }
+}
+
+fn describe_char(c: char) -> String {
+    match c {
+        '\n' => "newline".to_string(),
+        c => format!("'{}'", c),
+    }
+}
+
+fn describe_expected(expected: &[String]) -> String {
+    let tokens: Vec<String> = expected.iter().map(|token| describe(token)).collect();
+    match tokens.len() {
+        1 => tokens[0].clone(),
+        _ => format!("one of {}", tokens.join(" ")),
+    }
 }
 
 impl ParseError {
-    pub fn invalid_value(pos: usize, message: String) -> ParseError {
-        ParseError::InvalidValue(InvalidValue { pos, message })
+    /// The input ended at `pos` while one of `expected` was needed.
+    pub fn unexpected_end(pos: usize, expected: &[&str]) -> ParseError {
+        ParseError::UnexpectedEnd(UnexpectedEnd {
+            pos,
+            expected: expected.iter().map(|s| s.to_string()).collect(),
+            context: vec![],
+        })
+    }
+
+    /// An identifier was expected at `pos` but starts with `found`.
+    pub fn invalid_identifier(pos: usize, found: char) -> ParseError {
+        ParseError::InvalidValue(InvalidValue {
+            pos,
+            code: ErrorCode::InvalidIdentifier,
+            expected: vec!["identifier".to_string()],
+            found: describe_char(found),
+            context: vec![],
+        })
+    }
+
+    /// `found` was met at `pos` instead of one of `expected`.
+    pub fn unexpected_token(pos: usize, expected: &[&str], found: char) -> ParseError {
+        ParseError::InvalidValue(InvalidValue {
+            pos,
+            code: ErrorCode::UnexpectedToken,
+            expected: expected.iter().map(|s| s.to_string()).collect(),
+            found: describe_char(found),
+            context: vec![],
+        })
     }
 
-    pub fn unexpected_end(pos: usize) -> ParseError {
-        ParseError::UnexpectedEnd(UnexpectedEnd { pos })
+    /// The input is `size` bytes long, more than the allowed `max`.
+    pub fn input_too_large(size: usize, max: usize) -> ParseError {
+        ParseError::InvalidValue(InvalidValue {
+            pos: 0,
+            code: ErrorCode::InputTooLarge,
+            expected: vec![format!("at most {} bytes", max)],
+            found: format!("{} bytes", size),
+            context: vec![],
+        })
+    }
+
+    /// Move the error to another offset.
+    pub(crate) fn map_pos(mut self, f: impl FnOnce(usize) -> usize) -> ParseError {
+        match &mut self {
+            ParseError::UnexpectedEnd(e) => e.pos = f(e.pos),
+            ParseError::InvalidValue(e) => e.pos = f(e.pos),
+        }
+        self
+    }
+
+    /// Replace the set of tokens that would have been accepted.
+    pub fn with_expected(mut self, tokens: &[&str]) -> ParseError {
+        let expected = match &mut self {
+            ParseError::UnexpectedEnd(e) => &mut e.expected,
+            ParseError::InvalidValue(e) => &mut e.expected,
+        };
+        *expected = tokens.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Record the enclosing construct, e.g. "argument `x`", while the error bubbles up.
+    pub fn with_context(mut self, frame: String) -> ParseError {
+        match &mut self {
+            ParseError::UnexpectedEnd(e) => e.context.push(frame),
+            ParseError::InvalidValue(e) => e.context.push(frame),
+        }
+        self
     }
 
-    pub fn format(&self) -> String {
+    /// Byte offset of the error in the input.
+    pub fn pos(&self) -> usize {
         match self {
-            ParseError::UnexpectedEnd(e) => format!("Unexpected end at {}", e.pos),
-            ParseError::InvalidValue(e) => format!("Invalid value at {}: {}", e.pos, e.message),
+            ParseError::UnexpectedEnd(e) => e.pos,
+            ParseError::InvalidValue(e) => e.pos,
         }
     }
-}
 
-pub fn show_parse_error(input: &str, e: &ParseError) -> String {
-    let mut res = String::new();
-    let mut current_pos = 0;
-    let mut line_endings = vec![];
-    let mut attach_error_line = |line: &str, line_start: usize, line_end: usize| {
-        match e {
-            ParseError::UnexpectedEnd(ue) => {
-                if line_end >= ue.pos {
-                    res.push_str(&format!("{}\n", line));
-                    res.push_str(&format!("{}^", " ".repeat(ue.pos - line_start - 1)));
-                    res.push_str(&format!("{}\n", e.format()));
-                    return Some(());
-                }
+    /// The stable code of the error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ParseError::UnexpectedEnd(_) => ErrorCode::UnexpectedEnd,
+            ParseError::InvalidValue(e) => e.code,
+        }
+    }
+
+    /// What the parser would have accepted at `pos`.
+    pub fn expected_tokens(&self) -> &[String] {
+        match self {
+            ParseError::UnexpectedEnd(e) => &e.expected,
+            ParseError::InvalidValue(e) => &e.expected,
+        }
+    }
+
+    /// What was found at `pos` instead, `None` at the end of the input.
+    pub fn found(&self) -> Option<&str> {
+        match self {
+            ParseError::UnexpectedEnd(_) => None,
+            ParseError::InvalidValue(e) => Some(&e.found),
+        }
+    }
+
+    /// Enclosing constructs, innermost first.
+    pub fn context(&self) -> &[String] {
+        match self {
+            ParseError::UnexpectedEnd(e) => &e.context,
+            ParseError::InvalidValue(e) => &e.context,
+        }
+    }
+
+    /// A one line description, e.g. "expected ':', found 'D'".
+    pub fn message(&self) -> String {
+        let expected = self.expected_tokens();
+        match self {
+            ParseError::UnexpectedEnd(_) if expected.is_empty() => {
+                "unexpected end of input".to_string()
             }
-            ParseError::InvalidValue(iv) => {
-                if line_end >= iv.pos {
-                    res.push_str(&format!("{}\n", line));
-                    res.push_str(&format!("{}^", " ".repeat(iv.pos - line_start - 1)));
-                    res.push_str(&format!("{}\n", e.format()));
-                    return Some(());
-                }
+            ParseError::UnexpectedEnd(_) => format!(
+                "unexpected end of input, expected {}",
+                describe_expected(expected)
+            ),
+            ParseError::InvalidValue(e) if e.code == ErrorCode::InvalidIdentifier => {
+                format!("invalid identifier start {}", e.found)
             }
+            ParseError::InvalidValue(e) => format!(
+                "expected {}, found {}",
+                describe_expected(expected),
+                e.found
+            ),
         }
-        None
-    };
-    while let Some(line_ending) = input[current_pos..].find('\n') {
-        let line = &input[current_pos..current_pos + line_ending];
-        line_endings.push(current_pos + line_ending);
-        if attach_error_line(line, current_pos, current_pos + line_ending).is_some() {
-            break;
+    }
+
+    /// The context stack as a sentence, e.g. "in argument `x` of section `Args`".
+    pub fn context_message(&self) -> Option<String> {
+        let context = self.context();
+        if context.is_empty() {
+            None
+        } else {
+            Some(format!("in {}", context.join(" of ")))
         }
-        current_pos += line_ending + 1;
+    }
+}
+
+/// Render the error with the line of `input` it points at.
+pub fn show_parse_error(input: &str, e: &ParseError) -> String {
+    let diagnostic = Diagnostic::from_error(e, &LineIndex::new(input));
+    render_diagnostic(input, &diagnostic, &RenderOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        assert_eq!(
+            ParseError::unexpected_token(3, &[":"], 'D').message(),
+            "expected ':', found 'D'"
+        );
+        assert_eq!(
+            ParseError::unexpected_token(3, &["(", ":"], '\n').message(),
+            "expected one of '(' ':', found newline"
+        );
+        assert_eq!(
+            ParseError::invalid_identifier(0, '-').message(),
+            "invalid identifier start '-'"
+        );
+        assert_eq!(
+            ParseError::input_too_large(20, 10).message(),
+            "expected at most 10 bytes, found 20 bytes"
+        );
+        assert_eq!(
+            ParseError::unexpected_end(3, &[")"]).message(),
+            "unexpected end of input, expected ')'"
+        );
     }
 
-    attach_error_line(&input[current_pos..], current_pos, input.len());
-    res
+    #[test]
+    fn test_context() {
+        let err = ParseError::unexpected_token(3, &[":"], 'D')
+            .with_context("argument `x`".to_string())
+            .with_context("section `Args`".to_string());
+        assert_eq!(err.code().as_str(), "E003");
+        assert_eq!(
+            err.context_message(),
+            Some("in argument `x` of section `Args`".to_string())
+        );
+    }
 }
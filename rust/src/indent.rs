@@ -1,11 +1,101 @@
-static TAB_SIZE: usize = 4;
+//! The indentation model shared by every module: a tab advances to the next
+//! multiple of the tab size, like Python's `str.expandtabs`.
+
+use crate::utils::line_ranges;
+
+/// Tab size of [`crate::ParseOptions`] and [`crate::cleandoc`] by default.
+pub const DEFAULT_TAB_SIZE: usize = 4;
 
 pub fn is_indent_char(s: char) -> bool {
     s == ' ' || s == '\t'
 }
 
-pub fn calc_indent_size(s: &str) -> usize {
+/// Column reached after `c` when it is written at column `col`.
+pub fn advance(col: usize, c: char, tab_size: usize) -> usize {
+    if c == '\t' {
+        if tab_size == 0 {
+            col
+        } else {
+            col + tab_size - col % tab_size
+        }
+    } else {
+        col + 1
+    }
+}
+
+/// Width of the leading indentation of `s`.
+pub fn calc_indent_size(s: &str, tab_size: usize) -> usize {
     s.chars()
-        .map(|c| if c == '\t' { TAB_SIZE } else { 1 })
-        .sum()
+        .take_while(|c| is_indent_char(*c))
+        .fold(0, |col, c| advance(col, c, tab_size))
+}
+
+/// Number of leading spaces of `s`, the width of the indentation of the lines
+/// of a [`crate::Docstring`], which is expanded to spaces.
+pub fn leading_spaces(s: &str) -> usize {
+    s.len() - s.trim_start_matches(' ').len()
+}
+
+/// `s` with its leading indentation turned into spaces.
+pub fn expand_indent(s: &str, tab_size: usize) -> String {
+    " ".repeat(calc_indent_size(s, tab_size)) + s.trim_start_matches(is_indent_char)
+}
+
+/// Byte offset in `s` where its indentation reaches `width` columns, or the
+/// start of the tab crossing that column.
+pub fn indent_offset(s: &str, width: usize, tab_size: usize) -> usize {
+    let mut col = 0;
+    for (i, c) in s.char_indices() {
+        if !is_indent_char(c) || advance(col, c, tab_size) > width {
+            return i;
+        }
+        col = advance(col, c, tab_size);
+        if col == width {
+            return i + c.len_utf8();
+        }
+    }
+    s.len()
+}
+
+/// Byte ranges of the indentations using tabs where the first indented line
+/// uses spaces, or the other way around, and of the ones mixing both.
+pub fn inconsistent_indents(input: &str) -> Vec<(usize, usize)> {
+    let mut style = None;
+    let mut ranges = vec![];
+    for (start, end) in line_ranges(input) {
+        let line = &input[start..end];
+        let indent = &line[..line.len() - line.trim_start_matches(is_indent_char).len()];
+        let has_tab = indent.contains('\t');
+        let has_space = indent.contains(' ');
+        if indent.len() < line.len() && (has_tab || has_space) {
+            let mixed = has_tab && has_space;
+            let expected = *style.get_or_insert(has_tab);
+            if mixed || has_tab != expected {
+                ranges.push((start, start + indent.len()));
+            }
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_stops() {
+        assert_eq!(calc_indent_size("\tx", 4), 4);
+        assert_eq!(calc_indent_size("  \tx", 4), 4);
+        assert_eq!(calc_indent_size("    \t  x", 4), 10);
+        assert_eq!(indent_offset("  \tx", 4, 4), 3);
+        assert_eq!(indent_offset("  \tx", 2, 4), 2);
+        assert_eq!(indent_offset("  \tx", 3, 4), 2);
+    }
+
+    #[test]
+    fn test_inconsistent_indents() {
+        let input = "Args:\n    x: The x.\n\ty: The y.\n  \tz: The z.\n\t\n";
+        assert_eq!(inconsistent_indents(input), vec![(20, 21), (31, 34)]);
+        assert!(inconsistent_indents("Args:\n\tx: The x.\n\t\ty: The y.").is_empty());
+    }
 }
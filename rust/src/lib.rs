@@ -1,136 +1,1695 @@
+//! A parser for Google style Python docstrings.
+//!
+//! ```
+//! use docstring_parser::{parse, SectionKind};
+//!
+//! let docstring = parse("Summary.\n\nArgs:\n    x (int): The x.\n").unwrap();
+//! assert_eq!(docstring.summary(), Some("Summary."));
+//! assert_eq!(docstring.param("x").unwrap().r#type.as_deref(), Some("int"));
+//! assert_eq!(docstring.sections_of(SectionKind::Args).count(), 1);
+//! ```
+//!
+//! Malformed input is reported as a [`ParseError`], or as a list of
+//! [`Diagnostic`]s by [`parse_with_diagnostics`] which keeps going.
+//!
+//! The AST is derived from a lossless syntax tree, which [`parse_cst`] gives
+//! to tools that edit docstrings in place.
+//!
+//! Cargo features: `serde` for JSON support, `json-schema` for the JSON Schema
+//! of it and `python` for the Python bindings.
+
+#![warn(missing_docs)]
+
 mod context;
+// The cursor offers more scanning methods than the parser uses.
+#[allow(dead_code)]
 mod cursor;
-mod error;
+pub mod diagnostic;
+pub mod error;
 mod guard;
 mod indent;
+mod options;
 mod parser;
-mod schema;
+pub mod render;
+pub mod schema;
+pub mod span;
 mod utils;
+
 use crate::context::Context;
 use crate::cursor::Cursor;
-use crate::error::ParseError;
-use crate::parser::docstring::parse_docstring;
-use crate::schema::{Argument, Docstring, DocstringParagraph};
-use pyo3::exceptions::PyValueError;
-use pyo3::prelude::*;
-
-#[pyclass]
-struct PyArgument {
-    inner: Argument,
+use crate::indent::inconsistent_indents;
+use crate::parser::docstring::{
+    docstring_node, lower_docstring, parse_docstring, parse_docstring_recovering,
+};
+use crate::span::LineIndex;
+use crate::utils::Normalized;
+
+pub use crate::diagnostic::{render_diagnostic, Diagnostic, RenderOptions, Severity};
+pub use crate::error::{ErrorCode, ParseError};
+pub use crate::options::{ParseOptions, Strictness, Style};
+pub use crate::parser::cst::{CstElement, CstNode, CstToken, SyntaxKind};
+pub use crate::parser::docstring::DocstringTitle;
+pub use crate::schema::{
+    Argument, Block, BlockKind, CodeBlock, DefinitionItem, Directive, DirectiveKind, Docstring,
+    DocstringParagraph, Doctest, ExampleItem, Inline, Section, SectionKind, VersionChange,
+    VersionChangeKind,
+};
+pub use crate::span::{Position, Span};
+pub use crate::utils::cleandoc;
+
+/// Parse a docstring with the default options.
+pub fn parse(input: &str) -> Result<Docstring, ParseError> {
+    parse_with(input, &ParseOptions::default())
 }
 
-#[pymethods]
-impl PyArgument {
-    fn __repr__(&self) -> PyResult<String> {
-        Ok(format!("Argument({:?})", self.inner))
-    }
+/// Parse a docstring, failing on the first malformed entry unless
+/// [`ParseOptions::strictness`] is lenient.
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Docstring, ParseError> {
+    check_size(input, options)?;
+    let normalized = normalize(input, options);
+    let source = normalized.as_ref().map_or(input, |n| n.text.as_str());
+    let mut cursor = Cursor::new(source);
+    let mut ctx = Context::new(0, options);
+    ctx.recover = options.strictness == Strictness::Lenient;
+    ctx.normalized = normalized.as_ref();
+    parse_docstring(&mut cursor, &mut ctx).map_err(|err| match &normalized {
+        Some(normalized) => err.map_pos(|pos| normalized.offset(pos)),
+        None => err,
+    })
 }
 
-impl IntoPy<PyArgument> for Argument {
-    fn into_py(self, _py: Python) -> PyArgument {
-        PyArgument { inner: self }
+/// Parse as much of a docstring as possible, skipping malformed entries and
+/// reporting each of them as a diagnostic.
+pub fn parse_with_diagnostics(input: &str, options: &ParseOptions) -> (Docstring, Vec<Diagnostic>) {
+    if let Err(err) = check_size(input, options) {
+        return (
+            Docstring::default(),
+            vec![Diagnostic::from_error(&err, &LineIndex::new(input))],
+        );
     }
+    let normalized = normalize(input, options);
+    let source = normalized.as_ref().map_or(input, |n| n.text.as_str());
+    let mut cursor = Cursor::new(source);
+    let mut ctx = Context::new(0, options);
+    ctx.normalized = normalized.as_ref();
+    let (docstring, mut diagnostics) = parse_docstring_recovering(&mut cursor, &mut ctx);
+    let index = LineIndex::new(input);
+    diagnostics.extend(
+        inconsistent_indents(input)
+            .into_iter()
+            .map(|(start, end)| Diagnostic::inconsistent_indent(index.span(start, end))),
+    );
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    (docstring, diagnostics)
+}
+
+/// Parse a docstring into its lossless syntax tree with the default options.
+pub fn parse_cst(input: &str) -> Result<CstNode, ParseError> {
+    parse_cst_with(input, &ParseOptions::default())
 }
 
-#[pyclass]
-enum PyDocstringParagraphType {
-    Args,
-    Returns,
-    Note,
-    Warning,
-    Examples,
-    Raw,
+/// Parse a docstring into its lossless syntax tree, which prints back to
+/// `input` byte for byte, so that tools can edit one token and write the
+/// docstring back with a minimal diff.
+///
+/// The tree covers `input` as written: [`ParseOptions::cleandoc`] is not
+/// applied and `\r\n` and `\r` line breaks are kept in their tokens. Malformed entries become [`SyntaxKind::Error`] nodes when
+/// [`ParseOptions::strictness`] is lenient.
+pub fn parse_cst_with(input: &str, options: &ParseOptions) -> Result<CstNode, ParseError> {
+    check_size(input, options)?;
+    let mut cursor = Cursor::new(input);
+    let mut ctx = Context::new(0, options);
+    ctx.recover = options.strictness == Strictness::Lenient;
+    docstring_node(&mut cursor, &mut ctx)
 }
-#[pyclass]
-struct PyDocstringParagraph {
-    inner: DocstringParagraph,
+
+/// Derive the AST from a syntax tree, edited or not. Spans point into the
+/// printed tree.
+pub fn lower_cst(cst: &CstNode, options: &ParseOptions) -> Docstring {
+    let mut cst = cst.clone();
+    cst.renumber(&mut 0);
+    let text = cst.to_string();
+    lower_docstring(&cst, &LineIndex::new(&text), options)
 }
 
-impl IntoPy<PyDocstringParagraph> for DocstringParagraph {
-    fn into_py(self, _py: Python) -> PyDocstringParagraph {
-        PyDocstringParagraph { inner: self }
+/// The text to actually parse: trimmed if the options ask for it, with its
+/// line breaks turned into `\n` in any case. A section heading on the first
+/// line keeps the body of its section indented.
+fn normalize<'a>(input: &'a str, options: &ParseOptions) -> Option<Normalized<'a>> {
+    if options.cleandoc {
+        let first = input.split(['\n', '\r']).next().unwrap_or_default();
+        let heading = options.section_of(first).is_some();
+        Some(Normalized::new(input, options.tab_size, heading))
+    } else if input.contains('\r') {
+        Some(Normalized::line_breaks(input))
+    } else {
+        None
     }
 }
 
-#[pymethods]
-impl PyDocstringParagraph {
-    fn __repr__(&self) -> PyResult<String> {
-        Ok(format!("DocstringParagraph({:?})", self.inner))
+fn check_size(input: &str, options: &ParseOptions) -> Result<(), ParseError> {
+    match options.max_input_size {
+        Some(max) if input.len() > max => Err(ParseError::input_too_large(input.len(), max)),
+        _ => Ok(()),
     }
+}
+
+#[cfg(feature = "python")]
+mod python {
+    use crate::diagnostic::{render_diagnostic, Diagnostic, RenderOptions};
+    use crate::error::{show_parse_error, ParseError};
+    use crate::options::{ParseOptions, Strictness, Style};
+    use crate::parser::cst::{CstElement, CstNode, CstToken};
+    use crate::parser::docstring::DocstringTitle;
+    use crate::render::google::{render_google, FormatOptions};
+    use crate::render::html::{render_html_with, HtmlOptions, Resolve};
+    use crate::render::markdown::{render_markdown_with, MarkdownOptions, ParamLayout};
+    use crate::schema::{
+        Argument, Block, BlockKind, CodeBlock, Directive, DirectiveKind, Docstring,
+        DocstringParagraph, Doctest, ExampleItem, Inline, Section, SectionKind, VersionChange,
+        VersionChangeKind, SCHEMA_VERSION,
+    };
+    use crate::span::{LineIndex, Span};
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyIndexError;
+    use pyo3::exceptions::PyValueError;
+    use pyo3::prelude::*;
+    use pyo3::types::{PyIterator, PyList, PySlice};
+    use std::collections::HashMap;
 
-    #[pyo3(name = "r#type")]
-    fn type_(&self) -> PyResult<PyDocstringParagraphType> {
-        match self.inner {
-            DocstringParagraph::Args(_) => Ok(PyDocstringParagraphType::Args),
-            DocstringParagraph::Returns(_) => Ok(PyDocstringParagraphType::Returns),
-            DocstringParagraph::Note(_) => Ok(PyDocstringParagraphType::Note),
-            DocstringParagraph::Warning(_) => Ok(PyDocstringParagraphType::Warning),
-            DocstringParagraph::Examples(_) => Ok(PyDocstringParagraphType::Examples),
-            DocstringParagraph::Raw(_) => Ok(PyDocstringParagraphType::Raw),
+    #[pyclass]
+    struct PySpan {
+        #[pyo3(get)]
+        start: usize,
+        #[pyo3(get)]
+        end: usize,
+        #[pyo3(get)]
+        lineno: usize,
+        #[pyo3(get)]
+        col: usize,
+        #[pyo3(get)]
+        end_lineno: usize,
+        #[pyo3(get)]
+        end_col: usize,
+    }
+
+    #[pymethods]
+    impl PySpan {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!(
+                "Span({}..{}, {}:{}-{}:{})",
+                self.start, self.end, self.lineno, self.col, self.end_lineno, self.end_col
+            ))
         }
     }
-}
 
-#[pyclass]
-struct PyDocstring {
-    inner: Docstring,
-}
+    impl IntoPy<PySpan> for Span {
+        fn into_py(self, _py: Python) -> PySpan {
+            PySpan {
+                start: self.start,
+                end: self.end,
+                lineno: self.start_pos.line,
+                col: self.start_pos.col,
+                end_lineno: self.end_pos.line,
+                end_col: self.end_pos.col,
+            }
+        }
+    }
 
-impl IntoPy<PyDocstring> for Docstring {
-    fn into_py(self, _py: Python) -> PyDocstring {
-        PyDocstring { inner: self }
+    #[pyclass]
+    struct PyArgument {
+        inner: Argument,
     }
-}
 
-#[pymethods]
-impl PyDocstring {
-    fn __getitem__(&self, index: usize) -> PyResult<PyDocstringParagraph> {
-        Python::with_gil(|py| {
+    #[pymethods]
+    impl PyArgument {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Argument({:?})", self.inner))
+        }
+
+        #[getter]
+        fn name(&self) -> String {
+            self.inner.name.clone()
+        }
+
+        #[getter]
+        fn r#type(&self) -> Option<String> {
+            self.inner.r#type.clone()
+        }
+
+        /// The description lines joined with newlines.
+        #[getter]
+        fn description(&self) -> String {
+            self.inner.desc.join("\n")
+        }
+
+        #[getter]
+        fn lines(&self) -> Vec<String> {
+            self.inner.desc.clone()
+        }
+
+        /// The inline markup of the description.
+        #[getter]
+        fn inlines(&self, py: Python) -> Vec<PyInline> {
+            self.inner
+                .inlines()
+                .into_iter()
+                .map(|node| node.into_py(py))
+                .collect()
+        }
+
+        /// The paragraphs, lists and other blocks of the description.
+        #[getter]
+        fn blocks(&self, py: Python) -> Vec<PyBlock> {
+            blocks(py, self.inner.blocks())
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+
+        #[getter]
+        fn name_span(&self, py: Python) -> PySpan {
+            self.inner.name_span.into_py(py)
+        }
+
+        #[getter]
+        fn type_span(&self, py: Python) -> Option<PySpan> {
+            self.inner.type_span.map(|span| span.into_py(py))
+        }
+
+        #[getter]
+        fn desc_spans(&self, py: Python) -> Vec<PySpan> {
             self.inner
-                .get(index)
-                .map_or(Err(PyValueError::new_err("Index out of range")), |s| {
-                    Ok(s.clone().into_py(py))
+                .desc_spans
+                .iter()
+                .map(|span| span.into_py(py))
+                .collect()
+        }
+    }
+
+    impl IntoPy<PyArgument> for Argument {
+        fn into_py(self, _py: Python) -> PyArgument {
+            PyArgument { inner: self }
+        }
+    }
+
+    #[pyclass(name = "DocstringParagraphType")]
+    #[derive(Clone, Copy)]
+    enum PyDocstringParagraphType {
+        Args,
+        Returns,
+        Raises,
+        Note,
+        Warning,
+        Examples,
+        Deprecated,
+        Raw,
+    }
+
+    impl From<SectionKind> for PyDocstringParagraphType {
+        fn from(kind: SectionKind) -> PyDocstringParagraphType {
+            match kind {
+                SectionKind::Args => PyDocstringParagraphType::Args,
+                SectionKind::Returns => PyDocstringParagraphType::Returns,
+                SectionKind::Raises => PyDocstringParagraphType::Raises,
+                SectionKind::Note => PyDocstringParagraphType::Note,
+                SectionKind::Warning => PyDocstringParagraphType::Warning,
+                SectionKind::Examples => PyDocstringParagraphType::Examples,
+                SectionKind::Deprecated => PyDocstringParagraphType::Deprecated,
+                SectionKind::Raw => PyDocstringParagraphType::Raw,
+            }
+        }
+    }
+
+    impl From<PyDocstringParagraphType> for SectionKind {
+        fn from(kind: PyDocstringParagraphType) -> SectionKind {
+            match kind {
+                PyDocstringParagraphType::Args => SectionKind::Args,
+                PyDocstringParagraphType::Returns => SectionKind::Returns,
+                PyDocstringParagraphType::Raises => SectionKind::Raises,
+                PyDocstringParagraphType::Note => SectionKind::Note,
+                PyDocstringParagraphType::Warning => SectionKind::Warning,
+                PyDocstringParagraphType::Examples => SectionKind::Examples,
+                PyDocstringParagraphType::Deprecated => SectionKind::Deprecated,
+                PyDocstringParagraphType::Raw => SectionKind::Raw,
+            }
+        }
+    }
+
+    #[pymethods]
+    impl PyDocstringParagraphType {
+        /// Hashable, so that types can key dicts.
+        fn __hash__(&self) -> u64 {
+            *self as u64
+        }
+    }
+
+    #[pyclass]
+    struct PyDoctest {
+        inner: Doctest,
+    }
+
+    #[pymethods]
+    impl PyDoctest {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Doctest({:?})", self.inner))
+        }
+
+        /// The source lines joined with newlines, without prompts.
+        #[getter]
+        fn source(&self) -> String {
+            self.inner.source.join("\n")
+        }
+
+        /// The expected output lines joined with newlines.
+        #[getter]
+        fn want(&self) -> String {
+            self.inner.want.join("\n")
+        }
+
+        #[getter]
+        fn options(&self) -> Vec<String> {
+            self.inner.options.clone()
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+    }
+
+    impl IntoPy<PyDoctest> for Doctest {
+        fn into_py(self, _py: Python) -> PyDoctest {
+            PyDoctest { inner: self }
+        }
+    }
+
+    #[pyclass]
+    struct PyCodeBlock {
+        inner: CodeBlock,
+    }
+
+    #[pymethods]
+    impl PyCodeBlock {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("CodeBlock({:?})", self.inner))
+        }
+
+        #[getter]
+        fn language(&self) -> Option<String> {
+            self.inner.language.clone()
+        }
+
+        /// The directive options, in order.
+        #[getter]
+        fn options(&self) -> Vec<(String, String)> {
+            self.inner.options.clone()
+        }
+
+        #[getter]
+        fn code(&self) -> String {
+            self.inner.code.clone()
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+    }
+
+    impl IntoPy<PyCodeBlock> for CodeBlock {
+        fn into_py(self, _py: Python) -> PyCodeBlock {
+            PyCodeBlock { inner: self }
+        }
+    }
+
+    #[pyclass]
+    struct PyDirective {
+        inner: Directive,
+    }
+
+    #[pymethods]
+    impl PyDirective {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Directive({:?})", self.inner))
+        }
+
+        #[getter]
+        fn name(&self) -> String {
+            self.inner.name.clone()
+        }
+
+        #[getter]
+        fn argument(&self) -> String {
+            self.inner.argument.clone()
+        }
+
+        /// The version of `deprecated`, `versionadded` and `versionchanged`.
+        #[getter]
+        fn version(&self) -> Option<String> {
+            match &self.inner.kind {
+                DirectiveKind::Deprecated { version }
+                | DirectiveKind::VersionAdded { version }
+                | DirectiveKind::VersionChanged { version } => Some(version.clone()),
+                _ => None,
+            }
+        }
+
+        /// The directive options, in order.
+        #[getter]
+        fn options(&self) -> Vec<(String, String)> {
+            self.inner.options.clone()
+        }
+
+        /// The content lines joined with newlines.
+        #[getter]
+        fn content(&self) -> String {
+            self.inner.content.join("\n")
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+    }
+
+    impl IntoPy<PyDirective> for Directive {
+        fn into_py(self, _py: Python) -> PyDirective {
+            PyDirective { inner: self }
+        }
+    }
+
+    #[pyclass]
+    struct PyInline {
+        inner: Inline,
+    }
+
+    #[pymethods]
+    impl PyInline {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Inline({:?})", self.inner))
+        }
+
+        /// `"text"`, `"literal"`, `"emphasis"`, `"strong"`, `"role"`, `"link"`
+        /// or `"footnote_ref"`.
+        #[getter]
+        fn kind(&self) -> &'static str {
+            match self.inner {
+                Inline::Text(_) => "text",
+                Inline::Literal(_) => "literal",
+                Inline::Emphasis(_) => "emphasis",
+                Inline::Strong(_) => "strong",
+                Inline::Role { .. } => "role",
+                Inline::Link { .. } => "link",
+                Inline::FootnoteRef(_) => "footnote_ref",
+            }
+        }
+
+        /// The text without markup.
+        #[getter]
+        fn text(&self) -> String {
+            self.inner.plain_text()
+        }
+
+        /// The role name, `None` for the other kinds.
+        #[getter]
+        fn role(&self) -> Option<String> {
+            match &self.inner {
+                Inline::Role { name, .. } => Some(name.clone()),
+                _ => None,
+            }
+        }
+
+        /// The link target, `None` for the other kinds.
+        #[getter]
+        fn target(&self) -> Option<String> {
+            match &self.inner {
+                Inline::Link { target, .. } => Some(target.clone()),
+                _ => None,
+            }
+        }
+
+        /// The nodes of an emphasis, empty for the other kinds.
+        #[getter]
+        fn children(&self, py: Python) -> Vec<PyInline> {
+            match &self.inner {
+                Inline::Emphasis(children) | Inline::Strong(children) => children
+                    .iter()
+                    .map(|node| node.clone().into_py(py))
+                    .collect(),
+                _ => vec![],
+            }
+        }
+    }
+
+    impl IntoPy<PyInline> for Inline {
+        fn into_py(self, _py: Python) -> PyInline {
+            PyInline { inner: self }
+        }
+    }
+
+    fn blocks(py: Python, blocks: Vec<Block>) -> Vec<PyBlock> {
+        blocks.into_iter().map(|block| block.into_py(py)).collect()
+    }
+
+    fn inlines(py: Python, nodes: &[Inline]) -> Vec<PyInline> {
+        nodes.iter().map(|node| node.clone().into_py(py)).collect()
+    }
+
+    #[pyclass]
+    struct PyBlock {
+        inner: Block,
+    }
+
+    #[pymethods]
+    impl PyBlock {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Block({:?})", self.inner.kind))
+        }
+
+        /// `"paragraph"`, `"bullet_list"`, `"ordered_list"`,
+        /// `"definition_list"`, `"literal_block"` or `"block_quote"`.
+        #[getter]
+        fn kind(&self) -> &'static str {
+            match self.inner.kind {
+                BlockKind::Paragraph(_) => "paragraph",
+                BlockKind::BulletList(_) => "bullet_list",
+                BlockKind::OrderedList { .. } => "ordered_list",
+                BlockKind::DefinitionList(_) => "definition_list",
+                BlockKind::LiteralBlock(_) => "literal_block",
+                BlockKind::BlockQuote(_) => "block_quote",
+            }
+        }
+
+        /// The inline markup of a paragraph, `None` for the other kinds.
+        #[getter]
+        fn inlines(&self, py: Python) -> Option<Vec<PyInline>> {
+            match &self.inner.kind {
+                BlockKind::Paragraph(nodes) => Some(inlines(py, nodes)),
+                _ => None,
+            }
+        }
+
+        /// The text of a literal block, `None` for the other kinds.
+        #[getter]
+        fn text(&self) -> Option<String> {
+            match &self.inner.kind {
+                BlockKind::LiteralBlock(text) => Some(text.clone()),
+                _ => None,
+            }
+        }
+
+        /// The number of the first item of an ordered list.
+        #[getter]
+        fn start(&self) -> Option<usize> {
+            match self.inner.kind {
+                BlockKind::OrderedList { start, .. } => Some(start),
+                _ => None,
+            }
+        }
+
+        /// The blocks of each item of a list, or of each definition of a
+        /// definition list, empty for the other kinds.
+        #[getter]
+        fn items(&self, py: Python) -> Vec<Vec<PyBlock>> {
+            match &self.inner.kind {
+                BlockKind::BulletList(items) | BlockKind::OrderedList { items, .. } => {
+                    items.iter().map(|item| blocks(py, item.clone())).collect()
+                }
+                BlockKind::DefinitionList(items) => items
+                    .iter()
+                    .map(|item| blocks(py, item.definition.clone()))
+                    .collect(),
+                _ => vec![],
+            }
+        }
+
+        /// The terms of a definition list, empty for the other kinds.
+        #[getter]
+        fn terms(&self, py: Python) -> Vec<Vec<PyInline>> {
+            match &self.inner.kind {
+                BlockKind::DefinitionList(items) => {
+                    items.iter().map(|item| inlines(py, &item.term)).collect()
+                }
+                _ => vec![],
+            }
+        }
+
+        /// The blocks of a block quote, empty for the other kinds.
+        #[getter]
+        fn children(&self, py: Python) -> Vec<PyBlock> {
+            match &self.inner.kind {
+                BlockKind::BlockQuote(children) => blocks(py, children.clone()),
+                _ => vec![],
+            }
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+    }
+
+    impl IntoPy<PyBlock> for Block {
+        fn into_py(self, _py: Python) -> PyBlock {
+            PyBlock { inner: self }
+        }
+    }
+
+    /// A node of the lossless syntax tree; `str()` gives back its exact text.
+    #[pyclass(name = "CstNode")]
+    struct PyCstNode {
+        inner: CstNode,
+        options: ParseOptions,
+    }
+
+    #[pymethods]
+    impl PyCstNode {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!(
+                "CstNode({:?}, {:?})",
+                self.inner.kind,
+                self.inner.to_string()
+            ))
+        }
+
+        fn __str__(&self) -> String {
+            self.inner.to_string()
+        }
+
+        /// The syntax kind, e.g. `"Argument"`.
+        #[getter]
+        fn kind(&self) -> String {
+            format!("{:?}", self.inner.kind)
+        }
+
+        /// Nodes and tokens, in source order.
+        #[getter]
+        fn children(&self, py: Python) -> PyResult<Vec<PyObject>> {
+            self.inner
+                .children
+                .iter()
+                .map(|child| match child {
+                    CstElement::Node(node) => {
+                        let node = PyCstNode {
+                            inner: node.clone(),
+                            options: self.options.clone(),
+                        };
+                        Ok(Py::new(py, node)?.into_py(py))
+                    }
+                    CstElement::Token(token) => {
+                        Ok(Py::new(py, token.clone().into_py(py))?.into_py(py))
+                    }
                 })
+                .collect()
+        }
+
+        /// Derive the AST, with the options the tree was parsed with.
+        fn to_docstring(&self, py: Python) -> PyDocstring {
+            crate::lower_cst(&self.inner, &self.options).into_py(py)
+        }
+    }
+
+    /// A token of the lossless syntax tree.
+    #[pyclass(name = "CstToken")]
+    struct PyCstToken {
+        inner: CstToken,
+    }
+
+    #[pymethods]
+    impl PyCstToken {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!(
+                "CstToken({:?}, {:?})",
+                self.inner.kind, self.inner.text
+            ))
+        }
+
+        fn __str__(&self) -> String {
+            self.inner.text.clone()
+        }
+
+        /// The syntax kind, e.g. `"Type"`.
+        #[getter]
+        fn kind(&self) -> String {
+            format!("{:?}", self.inner.kind)
+        }
+
+        #[getter]
+        fn text(&self) -> String {
+            self.inner.text.clone()
+        }
+
+        /// Byte offset of the token in the input.
+        #[getter]
+        fn offset(&self) -> usize {
+            self.inner.offset
+        }
+    }
+
+    impl IntoPy<PyCstToken> for CstToken {
+        fn into_py(self, _py: Python) -> PyCstToken {
+            PyCstToken { inner: self }
+        }
+    }
+
+    #[pyclass]
+    struct PyVersionChange {
+        inner: VersionChange,
+    }
+
+    #[pymethods]
+    impl PyVersionChange {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("VersionChange({:?})", self.inner))
+        }
+
+        /// `"deprecated"`, `"added"` or `"changed"`.
+        #[getter]
+        fn kind(&self) -> &'static str {
+            match self.inner.kind {
+                VersionChangeKind::Deprecated => "deprecated",
+                VersionChangeKind::Added => "added",
+                VersionChangeKind::Changed => "changed",
+            }
+        }
+
+        #[getter]
+        fn version(&self) -> Option<String> {
+            self.inner.version.clone()
+        }
+
+        /// The description lines joined with newlines.
+        #[getter]
+        fn description(&self) -> String {
+            self.inner.description.join("\n")
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+    }
+
+    impl IntoPy<PyVersionChange> for VersionChange {
+        fn into_py(self, _py: Python) -> PyVersionChange {
+            PyVersionChange { inner: self }
+        }
+    }
+
+    #[pyclass]
+    struct PyDocstringParagraph {
+        inner: Section,
+    }
+
+    impl IntoPy<PyDocstringParagraph> for Section {
+        fn into_py(self, _py: Python) -> PyDocstringParagraph {
+            PyDocstringParagraph { inner: self }
+        }
+    }
+
+    #[pymethods]
+    impl PyDocstringParagraph {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("DocstringParagraph({:?})", self.inner.paragraph))
+        }
+
+        #[getter]
+        fn r#type(&self) -> PyDocstringParagraphType {
+            self.inner.paragraph.kind().into()
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+
+        /// The body lines, empty for `Args` and `Raises`.
+        #[getter]
+        fn lines(&self) -> Vec<String> {
+            self.inner.paragraph.lines()
+        }
+
+        /// The body lines joined with newlines.
+        #[getter]
+        fn description(&self) -> String {
+            self.inner.paragraph.lines().join("\n")
+        }
+
+        /// The paragraphs, lists and other blocks of the text, `None` for
+        /// `Args`, `Raises` and `Examples`.
+        #[getter]
+        fn blocks(&self, py: Python) -> Option<Vec<PyBlock>> {
+            self.inner.blocks().map(|nodes| blocks(py, nodes))
+        }
+
+        /// The inline markup of the text, `None` for `Args`, `Raises` and
+        /// `Examples`.
+        #[getter]
+        fn inlines(&self, py: Python) -> Option<Vec<PyInline>> {
+            self.inner
+                .inlines()
+                .map(|nodes| nodes.into_iter().map(|node| node.into_py(py)).collect())
+        }
+
+        /// The doctests and code blocks of an `Examples` paragraph and the text
+        /// between them, as strings, `None` for the other types.
+        #[getter]
+        fn examples(&self, py: Python) -> PyResult<Option<Vec<PyObject>>> {
+            let Some(items) = self.inner.examples() else {
+                return Ok(None);
+            };
+            items
+                .into_iter()
+                .map(|item| match item {
+                    ExampleItem::Text { lines, .. } => Ok(lines.join("\n").into_py(py)),
+                    ExampleItem::Doctest(doctest) => {
+                        Ok(Py::new(py, doctest.into_py(py))?.into_py(py))
+                    }
+                    ExampleItem::CodeBlock(block) => {
+                        Ok(Py::new(py, block.into_py(py))?.into_py(py))
+                    }
+                })
+                .collect::<PyResult<_>>()
+                .map(Some)
+        }
+
+        /// The entries of an `Args` or `Raises` paragraph, `None` for the other types.
+        #[getter]
+        fn args(&self, py: Python) -> Option<Vec<PyArgument>> {
+            match &self.inner.paragraph {
+                DocstringParagraph::Args(args) | DocstringParagraph::Raises(args) => {
+                    Some(args.iter().map(|arg| arg.clone().into_py(py)).collect())
+                }
+                _ => None,
+            }
+        }
+
+        #[getter]
+        fn heading_span(&self, py: Python) -> Option<PySpan> {
+            self.inner.heading_span.map(|span| span.into_py(py))
+        }
+
+        #[getter]
+        fn line_spans(&self, py: Python) -> Vec<PySpan> {
+            self.inner
+                .line_spans
+                .iter()
+                .map(|span| span.into_py(py))
+                .collect()
+        }
+    }
+
+    #[derive(FromPyObject)]
+    enum SectionIndex<'py> {
+        Int(isize),
+        Slice(Bound<'py, PySlice>),
+    }
+
+    #[pyclass]
+    struct PyDocstring {
+        inner: Docstring,
+    }
+
+    impl IntoPy<PyDocstring> for Docstring {
+        fn into_py(self, _py: Python) -> PyDocstring {
+            PyDocstring { inner: self }
+        }
+    }
+
+    #[pymethods]
+    impl PyDocstring {
+        fn __len__(&self) -> usize {
+            self.inner.len()
+        }
+
+        /// Index like a list: negative indices count from the end and slices
+        /// return a new docstring.
+        fn __getitem__(&self, py: Python, index: SectionIndex) -> PyResult<PyObject> {
+            match index {
+                SectionIndex::Int(i) => {
+                    let len = self.inner.len() as isize;
+                    let i = if i < 0 { i + len } else { i };
+                    if i < 0 || i >= len {
+                        return Err(PyIndexError::new_err("docstring index out of range"));
+                    }
+                    Ok(
+                        Py::new(py, self.inner.sections()[i as usize].clone().into_py(py))?
+                            .into_py(py),
+                    )
+                }
+                SectionIndex::Slice(slice) => {
+                    let indices = slice.indices(self.inner.len() as std::os::raw::c_long)?;
+                    let sections: Docstring = (0..indices.slicelength)
+                        .map(|n| {
+                            self.inner.sections()[(indices.start + n * indices.step) as usize]
+                                .clone()
+                        })
+                        .collect();
+                    Ok(Py::new(py, sections.into_py(py))?.into_py(py))
+                }
+            }
+        }
+
+        fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+            let paragraphs = self
+                .inner
+                .iter()
+                .map(|section| Py::new(py, section.clone().into_py(py)))
+                .collect::<PyResult<Vec<Py<PyDocstringParagraph>>>>()?;
+            PyList::new_bound(py, paragraphs).as_any().iter()
+        }
+
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Docstring({:?})", self.inner.sections()))
+        }
+
+        #[getter]
+        fn summary(&self) -> Option<&str> {
+            self.inner.summary()
+        }
+
+        #[getter]
+        fn params(&self, py: Python) -> Vec<PyArgument> {
+            self.inner
+                .params()
+                .map(|arg| arg.clone().into_py(py))
+                .collect()
+        }
+
+        fn param(&self, py: Python, name: &str) -> Option<PyArgument> {
+            self.inner.param(name).map(|arg| arg.clone().into_py(py))
+        }
+
+        #[getter]
+        fn returns(&self) -> Option<Vec<String>> {
+            self.inner.returns().map(|lines| lines.to_vec())
+        }
+
+        #[getter]
+        fn raises(&self, py: Python) -> Vec<PyArgument> {
+            self.inner
+                .raises()
+                .map(|arg| arg.clone().into_py(py))
+                .collect()
+        }
+
+        #[getter]
+        fn examples(&self) -> Option<Vec<String>> {
+            self.inner.examples().map(|lines| lines.to_vec())
+        }
+
+        /// The code blocks of every section.
+        #[getter]
+        fn code_blocks(&self, py: Python) -> Vec<PyCodeBlock> {
+            self.inner
+                .code_blocks()
+                .into_iter()
+                .map(|block| block.into_py(py))
+                .collect()
+        }
+
+        /// The directives of every section.
+        #[getter]
+        fn directives(&self, py: Python) -> Vec<PyDirective> {
+            self.inner
+                .directives()
+                .into_iter()
+                .map(|directive| directive.into_py(py))
+                .collect()
+        }
+
+        /// The blocks of the lines before the first section.
+        #[getter]
+        fn description_blocks(&self, py: Python) -> Vec<PyBlock> {
+            blocks(py, self.inner.description())
+        }
+
+        /// The deprecations, additions and changes, in source order.
+        #[getter]
+        fn version_changes(&self, py: Python) -> Vec<PyVersionChange> {
+            self.inner
+                .version_changes()
+                .into_iter()
+                .map(|change| change.into_py(py))
+                .collect()
+        }
+
+        /// The first deprecation.
+        #[getter]
+        fn deprecation(&self, py: Python) -> Option<PyVersionChange> {
+            self.inner.deprecation().map(|change| change.into_py(py))
+        }
+
+        /// The doctests of all `Examples` sections.
+        #[getter]
+        fn doctests(&self, py: Python) -> Vec<PyDoctest> {
+            self.inner
+                .doctests()
+                .into_iter()
+                .map(|doctest| doctest.into_py(py))
+                .collect()
+        }
+
+        fn sections_of(
+            &self,
+            py: Python,
+            kind: PyDocstringParagraphType,
+        ) -> Vec<PyDocstringParagraph> {
+            self.inner
+                .sections_of(kind.into())
+                .map(|section| section.clone().into_py(py))
+                .collect()
+        }
+
+        /// Render as CommonMark, `layout` being `"table"` or `"list"`.
+        #[pyo3(signature = (*, heading_level=2, layout="table"))]
+        fn to_markdown(&self, heading_level: usize, layout: &str) -> PyResult<String> {
+            let layout = match layout {
+                "table" => ParamLayout::Table,
+                "list" => ParamLayout::List,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown layout: {:?}",
+                        layout
+                    )))
+                }
+            };
+            let options = MarkdownOptions {
+                heading_level,
+                layout,
+            };
+            Ok(render_markdown_with(&self.inner, &options))
+        }
+
+        /// Render as an HTML fragment. `resolve(role, target)` may return the
+        /// URL of a cross-reference such as ``:func:`paddle.add` ``, the first
+        /// exception it raises is raised again.
+        #[pyo3(signature = (*, id_prefix="", heading_level=2, resolve=None))]
+        fn to_html(
+            &self,
+            py: Python<'_>,
+            id_prefix: &str,
+            heading_level: usize,
+            resolve: Option<PyObject>,
+        ) -> PyResult<String> {
+            let error = std::cell::RefCell::new(None);
+            let html = {
+                let callback = |role: &str, target: &str| {
+                    if error.borrow().is_some() {
+                        return None;
+                    }
+                    let url = resolve
+                        .as_ref()?
+                        .call1(py, (role, target))
+                        .and_then(|url| url.extract::<Option<String>>(py));
+                    url.unwrap_or_else(|e| {
+                        *error.borrow_mut() = Some(e);
+                        None
+                    })
+                };
+                let options = HtmlOptions {
+                    id_prefix: id_prefix.to_string(),
+                    heading_level,
+                    resolve: resolve.is_some().then_some(&callback as Resolve),
+                };
+                render_html_with(&self.inner, &options)
+            };
+            match error.into_inner() {
+                Some(e) => Err(e),
+                None => Ok(html),
+            }
+        }
+
+        /// Serialize to the versioned JSON representation, pretty printed if
+        /// `indent` is given.
+        #[cfg(feature = "serde")]
+        #[pyo3(signature = (indent=None))]
+        fn to_json(&self, indent: Option<usize>) -> PyResult<String> {
+            let res = match indent {
+                None => serde_json::to_string(&self.inner),
+                Some(indent) => {
+                    let indent = " ".repeat(indent);
+                    let formatter =
+                        serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                    let mut buf = vec![];
+                    let mut serializer =
+                        serde_json::Serializer::with_formatter(&mut buf, formatter);
+                    serde::Serialize::serialize(&self.inner, &mut serializer)
+                        .map(|()| String::from_utf8(buf).expect("serde_json emits UTF-8"))
+                }
+            };
+            res.map_err(|e| PyValueError::new_err(e.to_string()))
+        }
+
+        #[cfg(feature = "serde")]
+        #[staticmethod]
+        fn from_json(py: Python, json: &str) -> PyResult<PyDocstring> {
+            let docstring: Docstring =
+                serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            Ok(docstring.into_py(py))
+        }
+
+        /// The JSON representation as plain Python objects.
+        #[cfg(feature = "serde")]
+        fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+            let json = self.to_json(None)?;
+            py.import_bound("json")?.call_method1("loads", (json,))
+        }
+    }
+
+    #[pyclass]
+    struct PyDiagnostic {
+        inner: Diagnostic,
+        source: String,
+    }
+
+    #[pymethods]
+    impl PyDiagnostic {
+        fn __repr__(&self) -> PyResult<String> {
+            Ok(format!("Diagnostic({:?})", self.inner))
+        }
+
+        #[getter]
+        fn message(&self) -> String {
+            self.inner.message.clone()
+        }
+
+        #[getter]
+        fn span(&self, py: Python) -> PySpan {
+            self.inner.span.into_py(py)
+        }
+
+        #[getter]
+        fn severity(&self) -> &'static str {
+            self.inner.severity.as_str()
+        }
+
+        #[getter]
+        fn code(&self) -> Option<&'static str> {
+            self.inner.code
+        }
+
+        #[getter]
+        fn notes(&self) -> Vec<String> {
+            self.inner.notes.clone()
+        }
+
+        #[pyo3(signature = (color=false))]
+        fn render(&self, color: bool) -> String {
+            render_diagnostic(&self.source, &self.inner, &RenderOptions { color })
+        }
+    }
+
+    create_exception!(_core, DocstringParseError, PyValueError);
+    create_exception!(_core, UnexpectedEndError, DocstringParseError);
+    create_exception!(_core, InvalidValueError, DocstringParseError);
+
+    fn set_error_fields(py: Python, py_err: &PyErr, input: &str, err: &ParseError) -> PyResult<()> {
+        let position = LineIndex::new(input).position(err.pos());
+        let value = py_err.value_bound(py);
+        value.setattr("pos", err.pos())?;
+        value.setattr("lineno", position.line)?;
+        value.setattr("col", position.col)?;
+        value.setattr("message", err.message())?;
+        value.setattr("expected", err.expected_tokens().to_vec())?;
+        value.setattr("found", err.found())?;
+        value.setattr("code", err.code().as_str())?;
+        value.setattr("context", err.context().to_vec())?;
+        value.setattr("docstring", input)?;
+        Ok(())
+    }
+
+    fn to_py_err(input: &str, err: ParseError) -> PyErr {
+        let rendered = show_parse_error(input, &err);
+        let py_err = match err {
+            ParseError::UnexpectedEnd(_) => UnexpectedEndError::new_err(rendered),
+            ParseError::InvalidValue(_) => InvalidValueError::new_err(rendered),
+        };
+        match Python::with_gil(|py| set_error_fields(py, &py_err, input, &err)) {
+            Ok(()) => py_err,
+            Err(e) => e,
+        }
+    }
+
+    fn section_title(name: &str) -> PyResult<DocstringTitle> {
+        match name {
+            "args" => Ok(DocstringTitle::Args),
+            "returns" => Ok(DocstringTitle::Returns),
+            "raises" => Ok(DocstringTitle::Raises),
+            "examples" => Ok(DocstringTitle::Examples),
+            "notes" => Ok(DocstringTitle::Notes),
+            "warnings" => Ok(DocstringTitle::Warnings),
+            "deprecated" => Ok(DocstringTitle::Deprecated),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown section: {:?}",
+                name
+            ))),
+        }
+    }
+
+    /// Build [`ParseOptions`] out of the keyword arguments shared by `parse`,
+    /// `parse_with_diagnostics`, `parse_cst` and `format_docstring`.
+    #[allow(clippy::too_many_arguments)]
+    fn parse_options(
+        style: &str,
+        tab_size: usize,
+        strict: bool,
+        cleandoc: bool,
+        sections: Option<Vec<String>>,
+        aliases: Option<HashMap<String, String>>,
+        max_input_size: Option<usize>,
+    ) -> PyResult<ParseOptions> {
+        let style = match style {
+            "google" => Style::Google,
+            _ => return Err(PyValueError::new_err(format!("unknown style: {:?}", style))),
+        };
+        let mut options = ParseOptions {
+            style,
+            tab_size,
+            strictness: if strict {
+                Strictness::Strict
+            } else {
+                Strictness::Lenient
+            },
+            cleandoc,
+            max_input_size,
+            ..ParseOptions::default()
+        };
+        if let Some(sections) = sections {
+            options.sections = sections
+                .iter()
+                .map(|name| section_title(name))
+                .collect::<PyResult<_>>()?;
+        }
+        for (heading, name) in aliases.unwrap_or_default() {
+            options.aliases.push((heading, section_title(&name)?));
+        }
+        Ok(options)
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (
+        input, *, style="google", tab_size=4, strict=true, cleandoc=true,
+        sections=None, aliases=None, max_input_size=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn parse(
+        input: &str,
+        style: &str,
+        tab_size: usize,
+        strict: bool,
+        cleandoc: bool,
+        sections: Option<Vec<String>>,
+        aliases: Option<HashMap<String, String>>,
+        max_input_size: Option<usize>,
+    ) -> PyResult<PyDocstring> {
+        let options = parse_options(
+            style,
+            tab_size,
+            strict,
+            cleandoc,
+            sections,
+            aliases,
+            max_input_size,
+        )?;
+        Python::with_gil(|py| {
+            let parsed = crate::parse_with(input, &options).map_err(|e| to_py_err(input, e))?;
+            Ok(parsed.into_py(py))
         })
     }
 
-    // fn __iter__(&self) -> PyResult<PyObject> {
-    //     Python::with_gil(|py| {
-    //         let list = PyList::empty(py);
-    //         for item in &self.inner {
-    //             let item: PyObject = item.clone().into_py(py).into();
-    //             list.append(item)?;
-    //         }
-    //         Ok(list.into())
-    //     })
-    // }
+    /// Like `parse`, but malformed entries are always skipped and reported.
+    #[pyfunction]
+    #[pyo3(signature = (
+        input, *, style="google", tab_size=4, cleandoc=true,
+        sections=None, aliases=None, max_input_size=None,
+    ))]
+    fn parse_with_diagnostics(
+        input: &str,
+        style: &str,
+        tab_size: usize,
+        cleandoc: bool,
+        sections: Option<Vec<String>>,
+        aliases: Option<HashMap<String, String>>,
+        max_input_size: Option<usize>,
+    ) -> PyResult<(PyDocstring, Vec<PyDiagnostic>)> {
+        let options = parse_options(
+            style,
+            tab_size,
+            false,
+            cleandoc,
+            sections,
+            aliases,
+            max_input_size,
+        )?;
+        Ok(Python::with_gil(|py| {
+            let (parsed, diagnostics) = crate::parse_with_diagnostics(input, &options);
+            (
+                parsed.into_py(py),
+                diagnostics
+                    .into_iter()
+                    .map(|inner| PyDiagnostic {
+                        inner,
+                        source: input.to_string(),
+                    })
+                    .collect(),
+            )
+        }))
+    }
+
+    /// Parse into the lossless syntax tree of `input` as written, without
+    /// trimming it whatever `cleandoc` says. Malformed entries become `Error`
+    /// nodes unless `strict`.
+    #[pyfunction]
+    #[pyo3(signature = (
+        input, *, style="google", tab_size=4, strict=true, cleandoc=true,
+        sections=None, aliases=None, max_input_size=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn parse_cst(
+        input: &str,
+        style: &str,
+        tab_size: usize,
+        strict: bool,
+        cleandoc: bool,
+        sections: Option<Vec<String>>,
+        aliases: Option<HashMap<String, String>>,
+        max_input_size: Option<usize>,
+    ) -> PyResult<PyCstNode> {
+        let options = parse_options(
+            style,
+            tab_size,
+            strict,
+            cleandoc,
+            sections,
+            aliases,
+            max_input_size,
+        )?;
+        let inner = crate::parse_cst_with(input, &options).map_err(|e| to_py_err(input, e))?;
+        Ok(PyCstNode { inner, options })
+    }
+
+    /// Parse `input` with the same keyword arguments as `parse` and render it
+    /// back in the Google style.
+    #[pyfunction]
+    #[pyo3(signature = (
+        input, indent=4, width=None, blank_lines=1, section_order=None, *,
+        style="google", tab_size=4, strict=true, cleandoc=true,
+        sections=None, aliases=None, max_input_size=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn format_docstring(
+        input: &str,
+        indent: usize,
+        width: Option<usize>,
+        blank_lines: usize,
+        section_order: Option<Vec<String>>,
+        style: &str,
+        tab_size: usize,
+        strict: bool,
+        cleandoc: bool,
+        sections: Option<Vec<String>>,
+        aliases: Option<HashMap<String, String>>,
+        max_input_size: Option<usize>,
+    ) -> PyResult<String> {
+        let parse_options = parse_options(
+            style,
+            tab_size,
+            strict,
+            cleandoc,
+            sections,
+            aliases,
+            max_input_size,
+        )?;
+        let parsed = crate::parse_with(input, &parse_options).map_err(|e| to_py_err(input, e))?;
+        let mut options = FormatOptions {
+            indent,
+            width,
+            blank_lines,
+            ..FormatOptions::default()
+        };
+        if let Some(section_order) = section_order {
+            options.section_order = section_order
+                .iter()
+                .map(|name| section_title(name))
+                .collect::<PyResult<_>>()?;
+        }
+        Ok(render_google(&parsed, &options))
+    }
 
-    fn __repr__(&self) -> PyResult<String> {
-        Ok(format!("Docstring({:?})", self.inner))
+    /// The JSON Schema (draft 2020-12) of `PyDocstring.to_json()`, as a dict.
+    #[cfg(feature = "json-schema")]
+    #[pyfunction]
+    fn json_schema(py: Python) -> PyResult<Bound<PyAny>> {
+        let schema = serde_json::to_string(&crate::schema::json_schema())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        py.import_bound("json")?.call_method1("loads", (schema,))
     }
-}
 
-impl std::convert::From<ParseError> for PyErr {
-    fn from(err: ParseError) -> PyErr {
-        PyValueError::new_err(err.format())
+    /// A Python module implemented in Rust.
+    #[pymodule]
+    #[pyo3(name = "_core")]
+    fn docstring_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        m.add_function(wrap_pyfunction!(parse, m)?)?;
+        m.add_function(wrap_pyfunction!(parse_with_diagnostics, m)?)?;
+        m.add_function(wrap_pyfunction!(parse_cst, m)?)?;
+        m.add_function(wrap_pyfunction!(format_docstring, m)?)?;
+        #[cfg(feature = "json-schema")]
+        m.add_function(wrap_pyfunction!(json_schema, m)?)?;
+        m.add_class::<PyCstNode>()?;
+        m.add_class::<PyCstToken>()?;
+        m.add_class::<PyDocstring>()?;
+        m.add_class::<PyDocstringParagraphType>()?;
+        m.add_class::<PyDoctest>()?;
+        m.add_class::<PyCodeBlock>()?;
+        m.add_class::<PyDirective>()?;
+        m.add_class::<PyVersionChange>()?;
+        m.add_class::<PyInline>()?;
+        m.add_class::<PyBlock>()?;
+        m.add("SCHEMA_VERSION", SCHEMA_VERSION)?;
+        m.add(
+            "DocstringParseError",
+            m.py().get_type_bound::<DocstringParseError>(),
+        )?;
+        m.add(
+            "UnexpectedEndError",
+            m.py().get_type_bound::<UnexpectedEndError>(),
+        )?;
+        m.add(
+            "InvalidValueError",
+            m.py().get_type_bound::<InvalidValueError>(),
+        )?;
+        Ok(())
     }
 }
 
-#[pyfunction]
-fn parse(input: &str) -> PyResult<PyDocstring> {
-    Python::with_gil(|py| {
-        let mut cursor = Cursor::new(input);
-        let mut ctx = Context::new(0);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::WithoutSpans;
 
-        let parsed = parse_docstring(&mut cursor, &mut ctx)?;
-        Ok(parsed.into_py(py))
-    })
-}
+    #[test]
+    fn test_prose_is_not_a_heading() {
+        let docstring =
+            parse("Raises ValueError when x is negative.\n\nArgs:\n    x: The x.\n").unwrap();
+        assert_eq!(
+            docstring.summary(),
+            Some("Raises ValueError when x is negative.")
+        );
+        assert_eq!(docstring.raises().count(), 0);
+        assert_eq!(docstring.params().count(), 1);
+
+        let docstring =
+            parse("\nDeprecated since 2.0, use g instead.\n\nArgs:\n    x: The x.\n").unwrap();
+        assert_eq!(
+            docstring.summary(),
+            Some("Deprecated since 2.0, use g instead.")
+        );
+        assert_eq!(docstring.sections_of(SectionKind::Deprecated).count(), 0);
+        assert_eq!(docstring.params().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        let doc = "
+            Summary.
 
-/// A Python module implemented in Rust.
-#[pymodule]
-#[pyo3(name = "_core")]
-fn docstring_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(parse, m)?)?;
-    Ok(())
+            Arguments:
+                x (int): The x.
+            Notes:
+                Some note.
+        ";
+        let options = ParseOptions {
+            cleandoc: true,
+            sections: vec![DocstringTitle::Notes],
+            aliases: vec![("Arguments".to_string(), DocstringTitle::Args)],
+            ..ParseOptions::default()
+        };
+        let docstring = parse_with(doc, &options).unwrap();
+        assert_eq!(docstring.summary(), Some("Summary."));
+        assert_eq!(docstring.param("x").unwrap().r#type.as_deref(), Some("int"));
+        assert_eq!(
+            docstring
+                .sections_of(SectionKind::Note)
+                .next()
+                .unwrap()
+                .paragraph,
+            DocstringParagraph::Note("Some note.".to_string())
+        );
+
+        let options = ParseOptions {
+            max_input_size: Some(4),
+            ..ParseOptions::default()
+        };
+        let err = parse_with("Summary.", &options).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InputTooLarge);
+        let (_, diagnostics) = parse_with_diagnostics("Summary.", &options);
+        assert_eq!(diagnostics[0].code, Some("E004"));
+
+        let doc = "\nArgs:\n    arg1 Description.\n    arg2: Fine.\n";
+        assert!(parse(doc).is_err());
+        let options = ParseOptions {
+            strictness: Strictness::Lenient,
+            ..ParseOptions::default()
+        };
+        let docstring = parse_with(doc, &options).unwrap();
+        assert!(docstring.param("arg1").is_none());
+        assert!(docstring.param("arg2").is_some());
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        let doc = "Summary.
+
+        Args:
+        \tx (int): The x.
+            y: The y
+        ";
+        let docstring = parse(doc).unwrap();
+        assert_eq!(docstring.summary(), Some("Summary."));
+        let x = docstring.param("x").unwrap();
+        assert_eq!(x.name_span.start, doc.find('x').unwrap());
+        assert_eq!(x.name_span.start_pos, Position { line: 4, col: 9 });
+        assert_eq!(
+            docstring.param("y").unwrap().desc_spans[0].end,
+            doc.find("The y").unwrap() + 5
+        );
+
+        let doc = "
+            Args:
+                x The x.
+        ";
+        let err = parse(doc).unwrap_err();
+        assert_eq!(err.pos(), doc.find("The").unwrap());
+        let (_, diagnostics) = parse_with_diagnostics(doc, &ParseOptions::default());
+        assert_eq!(diagnostics[0].span.start_pos, Position { line: 3, col: 18 });
+    }
+
+    #[test]
+    fn test_parse_heading_on_first_line() {
+        for doc in ["Args:\n    x (int): d", "Args:\n        x (int): d\n    "] {
+            let docstring = parse(doc).unwrap();
+            assert_eq!(docstring.sections().len(), 1, "{:?}", docstring);
+            let x = docstring.param("x").unwrap();
+            assert_eq!(x.r#type.as_deref(), Some("int"));
+            assert_eq!(x.desc, vec!["d"]);
+        }
+    }
+
+    #[test]
+    fn test_parse_tabs() {
+        let spaces = "Summary.\nArgs:\n    x (int): The x.\n        More.\n    y: The y.\n";
+        let tabs = "Summary.\nArgs:\n\tx (int): The x.\n\t  \tMore.\n  \ty: The y.\n";
+        for cleandoc in [false, true] {
+            let options = ParseOptions {
+                cleandoc,
+                ..ParseOptions::default()
+            };
+            let expected = parse_with(spaces, &options).unwrap();
+            let docstring = parse_with(tabs, &options).unwrap();
+            let params = |docstring: &Docstring| {
+                docstring
+                    .params()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .without_spans()
+            };
+            assert_eq!(params(&docstring), params(&expected));
+            let x = docstring.param("x").unwrap();
+            assert_eq!(x.desc_spans[1].start, tabs.find("More").unwrap());
+        }
+
+        let doc = "Summary.\n\n  Quoted.\n\tMore.";
+        for (tab_size, one_paragraph) in [(2, true), (8, false)] {
+            let options = ParseOptions {
+                tab_size,
+                cleandoc: false,
+                ..ParseOptions::default()
+            };
+            let blocks = parse_with(doc, &options).unwrap().description();
+            let BlockKind::BlockQuote(quote) = &blocks[1].kind else {
+                panic!("not a block quote: {:?}", blocks[1]);
+            };
+            assert_eq!(
+                matches!(
+                    quote[..],
+                    [Block {
+                        kind: BlockKind::Paragraph(_),
+                        ..
+                    }]
+                ),
+                one_paragraph
+            );
+        }
+
+        let (_, diagnostics) = parse_with_diagnostics(tabs, &ParseOptions::default());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].span.start_pos, Position { line: 4, col: 0 });
+        assert_eq!(diagnostics[1].span.start_pos, Position { line: 5, col: 0 });
+    }
+
+    #[test]
+    fn test_parse_line_breaks() {
+        let doc = "Summary.\r\n\r\nArgs:\r\n    x (int): The x.\r    y: The y.\r\n";
+        for cleandoc in [false, true] {
+            let options = ParseOptions {
+                cleandoc,
+                ..ParseOptions::default()
+            };
+            let docstring = parse_with(doc, &options).unwrap();
+            assert_eq!(docstring.summary(), Some("Summary."));
+            let x = docstring.param("x").unwrap();
+            assert_eq!(x.desc, vec!["The x."]);
+            assert_eq!(x.desc_spans[0].end, doc.find("\r    y").unwrap());
+            let y = docstring.param("y").unwrap();
+            assert_eq!(y.name_span.start, doc.find("y:").unwrap());
+            assert_eq!(y.name_span.start_pos, Position { line: 5, col: 4 });
+        }
+
+        for line_break in ["\r\n", "\r"] {
+            let doc = "Summary.\n\nArgs:\n    x (int): The x.\n\n        More.\n    y: The y.\n"
+                .replace('\n', line_break);
+            let cst = parse_cst(&doc).unwrap();
+            assert_eq!(cst.to_string(), doc);
+            let docstring = lower_cst(&cst, &ParseOptions::default());
+            assert_eq!(
+                docstring.sections()[0].paragraph,
+                DocstringParagraph::Raw("Summary.".to_string())
+            );
+            assert_eq!(docstring.param("x").unwrap().desc, ["The x.", "", "More."]);
+            assert_eq!(docstring.param("y").unwrap().desc, ["The y."]);
+        }
+
+        let doc = "\r\nArgs:\r\n    x The x.\r\n";
+        let (_, diagnostics) = parse_with_diagnostics(doc, &ParseOptions::default());
+        assert_eq!(diagnostics[0].span.start_pos, Position { line: 3, col: 6 });
+        assert!(
+            render_diagnostic(doc, &diagnostics[0], &RenderOptions::default())
+                .contains("3 |     x The x.\n")
+        );
+    }
 }
@@ -0,0 +1,115 @@
+use crate::indent::DEFAULT_TAB_SIZE;
+use crate::parser::docstring::DocstringTitle;
+
+/// The docstring conventions [`crate::parse_with`] understands.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Style {
+    /// <https://google.github.io/styleguide/pyguide.html#38-comments-and-docstrings>
+    #[default]
+    Google,
+}
+
+/// What to do with malformed entries.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Strictness {
+    /// Fail on the first malformed entry.
+    #[default]
+    Strict,
+    /// Skip malformed entries, as [`crate::parse_with_diagnostics`] does, but
+    /// without reporting them.
+    Lenient,
+}
+
+/// Options of [`crate::parse_with`] and [`crate::parse_with_diagnostics`].
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// The docstring convention.
+    pub style: Style,
+    /// Tabs advance to the next multiple of this many columns, when measuring
+    /// indentation and when trimming the input.
+    pub tab_size: usize,
+    /// What to do with malformed entries.
+    pub strictness: Strictness,
+    /// Trim the input as PEP 257 describes first, like `inspect.cleandoc`,
+    /// so that `__doc__` strings can be given as they are. Spans and errors
+    /// still point into the input.
+    pub cleandoc: bool,
+    /// Sections recognized by their built-in headings, in matching order.
+    /// Headings of other sections are kept as raw lines.
+    pub sections: Vec<DocstringTitle>,
+    /// Extra headings, e.g. `("Arguments", DocstringTitle::Args)`. They are
+    /// matched against the whole heading without its colon, before the
+    /// built-in ones and whether the section is enabled or not.
+    pub aliases: Vec<(String, DocstringTitle)>,
+    /// Refuse inputs longer than this many bytes.
+    pub max_input_size: Option<usize>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            style: Style::default(),
+            tab_size: DEFAULT_TAB_SIZE,
+            strictness: Strictness::default(),
+            cleandoc: true,
+            sections: vec![
+                DocstringTitle::Args,
+                DocstringTitle::Returns,
+                DocstringTitle::Raises,
+                DocstringTitle::Examples,
+                DocstringTitle::Deprecated,
+            ],
+            aliases: vec![],
+            max_input_size: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Find the section a line introduces, if any: the whole line, without
+    /// its colon, must be a heading, so that prose such as `Raises ValueError
+    /// when x is negative.` stays prose.
+    pub fn section_of(&self, line: &str) -> Option<DocstringTitle> {
+        let name = line.trim().trim_end_matches(':');
+        self.aliases
+            .iter()
+            .find(|(alias, _)| alias == name)
+            .map(|&(_, title)| title)
+            .or_else(|| {
+                self.sections
+                    .iter()
+                    .copied()
+                    .find(|title| title.heads().contains(&name))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_of() {
+        let mut options = ParseOptions::default();
+        assert_eq!(options.section_of("Args:"), Some(DocstringTitle::Args));
+        assert_eq!(options.section_of("Notes:"), None);
+        assert_eq!(options.section_of("Arguments:"), None);
+        assert_eq!(
+            options.section_of("  Returns:  "),
+            Some(DocstringTitle::Returns)
+        );
+        assert_eq!(options.section_of("Raises ValueError when x < 0."), None);
+        assert_eq!(options.section_of("Args: x and y."), None);
+        assert_eq!(options.section_of("Deprecated since 2.0."), None);
+
+        options.sections = vec![DocstringTitle::Notes];
+        options.aliases = vec![
+            ("Throws".to_string(), DocstringTitle::Raises),
+            ("Arguments".to_string(), DocstringTitle::Args),
+        ];
+        assert_eq!(options.section_of("Args:"), None);
+        assert_eq!(options.section_of("Notes:"), Some(DocstringTitle::Notes));
+        assert_eq!(options.section_of("Throws:"), Some(DocstringTitle::Raises));
+        assert_eq!(options.section_of("Arguments:"), Some(DocstringTitle::Args));
+    }
+}
@@ -1,72 +1,191 @@
 use crate::context::Context;
 use crate::cursor::Cursor;
-use crate::error::ParseError;
 use crate::guard::Guard;
 use crate::indent::{calc_indent_size, is_indent_char};
-use crate::parser::common::next_line;
-use crate::parser::common::{identifier, indented_paragraph, ParseResult};
+use crate::parser::common::{
+    blank_lines, indented_block, line, line_span, lower_block, next_indent, ParseResult,
+};
+use crate::parser::cst::{CstNode, SyntaxKind};
 use crate::schema::Argument;
-use crate::utils::cleandoc;
-use textwrap::indent;
+use crate::span::LineIndex;
 
-pub fn parse_arg<'a>(input: &'a mut Cursor, ctx: &mut Context) -> ParseResult<Argument> {
-    let name = identifier(input, ctx)?;
+/// Parses the name of an entry: an identifier for `Args`, a dotted path for
+/// `Raises`.
+pub type NameParser = fn(&mut Cursor, &mut Context) -> ParseResult<String>;
 
+pub fn argument_node(
+    input: &mut Cursor,
+    ctx: &mut Context,
+    name: NameParser,
+) -> ParseResult<CstNode> {
+    let mut node = CstNode::new(SyntaxKind::Argument);
+    let start = input.pos;
+    let name = name(input, ctx)?;
+    node.push_since(SyntaxKind::Name, input, start);
+    argument_rest(input, ctx, &mut node)
+        .map_err(|err| err.with_context(format!("argument `{}`", name)))?;
+    Ok(node)
+}
+
+/// Everything of an argument after its name.
+fn argument_rest(input: &mut Cursor, ctx: &mut Context, node: &mut CstNode) -> ParseResult<()> {
+    let start = input.pos;
     input.eat_whitespace();
-    let mut r#type: Option<String> = None;
-    if let Some(_c @ '(') = input.peek() {
+    node.push_since(SyntaxKind::Whitespace, input, start);
+    let has_type = input.peek() == Some('(');
+    if has_type {
+        let start = input.pos;
         input.eat_string("(")?;
-        r#type = Some(input.take_until(|c| c == ')').to_string());
+        node.push_since(SyntaxKind::LParen, input, start);
+        let start = input.pos;
+        input.take_until(|c| c == ')');
+        node.push_since(SyntaxKind::Type, input, start);
+        let start = input.pos;
         input.eat_string(")")?;
+        node.push_since(SyntaxKind::RParen, input, start);
+        let start = input.pos;
         input.eat_whitespace();
+        node.push_since(SyntaxKind::Whitespace, input, start);
     }
-    input.eat_string(":")?;
+    let start = input.pos;
+    input.eat_string(":").map_err(|err| {
+        if has_type {
+            err
+        } else {
+            err.with_expected(&["(", ":"])
+        }
+    })?;
+    node.push_since(SyntaxKind::Colon, input, start);
+    let start = input.pos;
     input.eat_whitespace();
-    let desc_head = next_line(input, ctx)?;
-    let mut desc: Vec<String> = vec![desc_head];
-    let next_indent_size = calc_indent_size(input.peek_while(is_indent_char));
-    if next_indent_size > ctx.indent {
-        desc.extend(indented_paragraph(input, ctx)?)
+    node.push_since(SyntaxKind::Whitespace, input, start);
+    node.push_node(line(input, ctx, SyntaxKind::Line)?);
+    // Paragraphs after blank lines continue the description as well.
+    if next_indent(input, ctx.options.tab_size).is_some_and(|indent| indent > ctx.indent) {
+        node.push_node(indented_block(input, ctx)?);
     }
-    Ok(Argument { name, r#type, desc })
+    Ok(())
 }
 
-pub fn parse_args<'a>(
-    input: &'a mut Cursor,
+/// Skip a malformed entry, that is its first line and everything indented deeper.
+fn error_node(input: &mut Cursor, ctx: &mut Context) -> ParseResult<CstNode> {
+    let mut node = CstNode::new(SyntaxKind::Error);
+    node.push_node(line(input, ctx, SyntaxKind::Line)?);
+    node.push_node(indented_block(input, ctx)?);
+    Ok(node)
+}
+
+pub fn arg_list_node(
+    input: &mut Cursor,
     ctx: &mut Context,
-) -> Result<Vec<Argument>, ParseError> {
-    let mut args: Vec<Argument> = vec![];
-    input.eat_empty_lines();
+    name: NameParser,
+) -> ParseResult<CstNode> {
+    let mut node = CstNode::new(SyntaxKind::ArgList);
+    blank_lines(input, &mut node);
     loop {
-        let next_indent_size = calc_indent_size(input.peek_while(is_indent_char));
+        let next_indent_size =
+            calc_indent_size(input.peek_while(is_indent_char), ctx.options.tab_size);
         if next_indent_size <= ctx.indent {
-            break Ok(args);
+            break Ok(node);
         }
 
-        let ctx_guard = ctx.guard(Context::new(next_indent_size));
+        let ctx_guard = ctx.guard(Context::new(next_indent_size, ctx.options));
         ctx.indent = next_indent_size;
+        let start = input.pos;
         input.eat_indent();
-        args.push(parse_arg(input, ctx)?);
+        node.push_since(SyntaxKind::Indent, input, start);
+        let cursor_guard = input.guard();
+        match argument_node(input, ctx, name) {
+            Ok(argument) => node.push_node(argument),
+            Err(err) if ctx.recover => {
+                cursor_guard.restore(input);
+                node.push_node(error_node(input, ctx)?);
+                ctx.errors.push(err);
+            }
+            Err(err) => return Err(err),
+        }
         ctx_guard.restore(ctx);
-        input.eat_empty_lines();
+        blank_lines(input, &mut node);
+    }
+}
+
+pub fn lower_argument(node: &CstNode, index: &LineIndex, tab_size: usize) -> Argument {
+    let token_text = |kind| node.token(kind).map_or("", |token| token.text.as_str());
+    let r#type = node
+        .token(SyntaxKind::LParen)
+        .map(|_| token_text(SyntaxKind::Type).to_string());
+    let type_span = node.token(SyntaxKind::LParen).map(|lparen| {
+        node.token(SyntaxKind::Type)
+            .map_or(index.span(lparen.end(), lparen.end()), |token| {
+                token.span(index)
+            })
+    });
+    let colon_end = node.token(SyntaxKind::Colon).map_or(0, |token| token.end());
+    let head = node.node(SyntaxKind::Line);
+    let mut desc = vec![head.map_or(String::new(), |line| line.content())];
+    let mut desc_spans = vec![head
+        .and_then(|line| line_span(line, 0, index))
+        .unwrap_or(index.span(colon_end, colon_end))];
+    if let Some(block) = node.node(SyntaxKind::Block) {
+        let (lines, spans) = lower_block(block, index, tab_size);
+        desc.extend(lines);
+        desc_spans.extend(spans);
+    }
+    Argument {
+        name: token_text(SyntaxKind::Name).to_string(),
+        r#type,
+        desc,
+        span: node.span(index).unwrap_or_default(),
+        name_span: node
+            .token(SyntaxKind::Name)
+            .map(|token| token.span(index))
+            .unwrap_or_default(),
+        type_span,
+        desc_spans,
     }
 }
 
+pub fn lower_arg_list(node: &CstNode, index: &LineIndex, tab_size: usize) -> Vec<Argument> {
+    node.nodes()
+        .filter(|child| child.kind == SyntaxKind::Argument)
+        .map(|argument| lower_argument(argument, index, tab_size))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::ParseOptions;
+    use crate::parser::common::{dotted_name, identifier};
+    use crate::schema::WithoutSpans;
+    use crate::utils::cleandoc;
+    use textwrap::indent;
+
+    fn parse_arg(input: &mut Cursor, ctx: &mut Context) -> ParseResult<Argument> {
+        let node = argument_node(input, ctx, identifier)?;
+        let index = LineIndex::new(input.input);
+        Ok(lower_argument(&node, &index, ctx.options.tab_size))
+    }
+
+    fn parse_args(input: &mut Cursor, ctx: &mut Context) -> ParseResult<Vec<Argument>> {
+        let node = arg_list_node(input, ctx, identifier)?;
+        let index = LineIndex::new(input.input);
+        Ok(lower_arg_list(&node, &index, ctx.options.tab_size))
+    }
 
     #[test]
     fn test_parse_arg() {
         let doc = "arg1 (int): Description of arg1";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
         assert_eq!(
-            parse_arg(&mut cursor, &mut ctx),
+            parse_arg(&mut cursor, &mut ctx).without_spans(),
             Ok(Argument {
                 name: "arg1".to_string(),
                 r#type: Some("int".to_string()),
-                desc: vec!["Description of arg1".to_string(),]
+                desc: vec!["Description of arg1".to_string(),],
+                ..Default::default()
             })
         );
     }
@@ -75,13 +194,15 @@ mod tests {
     fn test_parse_arg_no_type() {
         let doc = "arg1: Description of arg1\n";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
         assert_eq!(
-            parse_arg(&mut cursor, &mut ctx),
+            parse_arg(&mut cursor, &mut ctx).without_spans(),
             Ok(Argument {
                 name: "arg1".to_string(),
                 r#type: None,
-                desc: vec!["Description of arg1".to_string(),]
+                desc: vec!["Description of arg1".to_string(),],
+                ..Default::default()
             })
         );
     }
@@ -90,9 +211,10 @@ mod tests {
     fn test_parse_arg_multi_line() {
         let doc = "arg1 (int): Description of arg1\n    multi line\n    description\n";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(0);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
         assert_eq!(
-            parse_arg(&mut cursor, &mut ctx),
+            parse_arg(&mut cursor, &mut ctx).without_spans(),
             Ok(Argument {
                 name: "arg1".to_string(),
                 r#type: Some("int".to_string()),
@@ -100,7 +222,8 @@ mod tests {
                     "Description of arg1".to_string(),
                     "multi line".to_string(),
                     "description".to_string(),
-                ]
+                ],
+                ..Default::default()
             })
         );
     }
@@ -109,13 +232,15 @@ mod tests {
     fn test_parse_args() {
         let doc = "    arg1 (int): Description of arg1\n";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(0);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
         assert_eq!(
-            parse_args(&mut cursor, &mut ctx),
+            parse_args(&mut cursor, &mut ctx).without_spans(),
             Ok(vec![Argument {
                 name: "arg1".to_string(),
                 r#type: Some("int".to_string()),
-                desc: vec!["Description of arg1".to_string(),]
+                desc: vec!["Description of arg1".to_string(),],
+                ..Default::default()
             }])
         );
     }
@@ -124,9 +249,10 @@ mod tests {
     fn test_parse_args_multi_line() {
         let doc = "    arg1 (int): Description of arg1\n        multi line\n        description\n";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(0);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
         assert_eq!(
-            parse_args(&mut cursor, &mut ctx),
+            parse_args(&mut cursor, &mut ctx).without_spans(),
             Ok(vec![Argument {
                 name: "arg1".to_string(),
                 r#type: Some("int".to_string()),
@@ -134,7 +260,8 @@ mod tests {
                     "Description of arg1".to_string(),
                     "multi line".to_string(),
                     "description".to_string(),
-                ]
+                ],
+                ..Default::default()
             }])
         );
     }
@@ -143,19 +270,22 @@ mod tests {
     fn test_parse_args_multi_args() {
         let doc = "    arg1 (int): Description of arg1\n    arg2 (str): Description of arg2\n";
         let mut cursor = Cursor::new(doc);
-        let mut ctx = Context::new(0);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
         assert_eq!(
-            parse_args(&mut cursor, &mut ctx),
+            parse_args(&mut cursor, &mut ctx).without_spans(),
             Ok(vec![
                 Argument {
                     name: "arg1".to_string(),
                     r#type: Some("int".to_string()),
-                    desc: vec!["Description of arg1".to_string(),]
+                    desc: vec!["Description of arg1".to_string(),],
+                    ..Default::default()
                 },
                 Argument {
                     name: "arg2".to_string(),
                     r#type: Some("str".to_string()),
-                    desc: vec!["Description of arg2".to_string(),]
+                    desc: vec!["Description of arg2".to_string(),],
+                    ..Default::default()
                 }
             ])
         );
@@ -180,9 +310,10 @@ mod tests {
             "    ",
         );
         let mut cursor = Cursor::new(&doc);
-        let mut ctx = Context::new(0);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
         assert_eq!(
-            parse_args(&mut cursor, &mut ctx),
+            parse_args(&mut cursor, &mut ctx).without_spans(),
             Ok(vec![
                 Argument {
                     name: "arg1".to_string(),
@@ -192,7 +323,8 @@ mod tests {
                         "multi line".to_string(),
                         "".to_string(),
                         "description".to_string(),
-                    ]
+                    ],
+                    ..Default::default()
                 },
                 Argument {
                     name: "arg2".to_string(),
@@ -201,9 +333,63 @@ mod tests {
                         "Description of arg2".to_string(),
                         "multi line".to_string(),
                         "description".to_string(),
-                    ]
+                    ],
+                    ..Default::default()
                 }
             ])
         );
     }
+
+    #[test]
+    fn test_parse_args_paragraphs() {
+        let doc = "    x (int): The x.\n\n        - a\n        - b\n\n    y: The y.\n";
+        let mut cursor = Cursor::new(doc);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        let args = parse_args(&mut cursor, &mut ctx).unwrap();
+        assert_eq!(args[0].desc, ["The x.", "", "- a", "- b"]);
+        assert_eq!(args[1].name, "y");
+    }
+
+    #[test]
+    fn test_parse_args_spans() {
+        let doc = "    arg1 (int): Description of arg1\n        multi line\n    arg2: Description of arg2\n";
+        let mut cursor = Cursor::new(doc);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        let args = parse_args(&mut cursor, &mut ctx).unwrap();
+        let index = LineIndex::new(doc);
+        assert_eq!(args[0].span, index.span(4, 54));
+        assert_eq!(args[0].name_span, index.span(4, 8));
+        assert_eq!(args[0].type_span, Some(index.span(10, 13)));
+        assert_eq!(
+            args[0].desc_spans,
+            vec![index.span(16, 35), index.span(44, 54)]
+        );
+        assert_eq!(args[0].desc_spans[1].start_pos.line, 2);
+        assert_eq!(args[0].desc_spans[1].start_pos.col, 8);
+        assert_eq!(args[1].name_span, index.span(59, 63));
+        assert_eq!(args[1].type_span, None);
+    }
+
+    #[test]
+    fn test_parse_qualified_names() {
+        let doc = "    requests.HTTPError: If the request fails.\n    os.error.Sub (x): Odd.\n";
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        let node = arg_list_node(&mut Cursor::new(doc), &mut ctx, dotted_name).unwrap();
+        let args = lower_arg_list(&node, &LineIndex::new(doc), 4);
+        assert_eq!(
+            args.iter().map(|arg| arg.name.as_str()).collect::<Vec<_>>(),
+            ["requests.HTTPError", "os.error.Sub"]
+        );
+        assert_eq!(args[0].name_span, LineIndex::new(doc).span(4, 22));
+
+        let mut ctx = Context::new(0, &options);
+        assert!(arg_list_node(&mut Cursor::new(doc), &mut ctx, identifier).is_err());
+        for doc in ["    requests.: Bad.\n", "    .HTTPError: Bad.\n"] {
+            let mut ctx = Context::new(0, &options);
+            assert!(arg_list_node(&mut Cursor::new(doc), &mut ctx, dotted_name).is_err());
+        }
+    }
 }
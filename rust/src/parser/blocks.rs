@@ -0,0 +1,357 @@
+use crate::indent::leading_spaces;
+use crate::parser::common::{cover, dedent};
+use crate::parser::inline::parse_inline;
+use crate::schema::{Block, BlockKind, DefinitionItem};
+use crate::span::Span;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Marker {
+    Bullet(char),
+    Ordered(usize),
+}
+
+impl Marker {
+    fn continues(self, first: Marker) -> bool {
+        match (self, first) {
+            (Marker::Bullet(c), Marker::Bullet(first)) => c == first,
+            (Marker::Ordered(_), Marker::Ordered(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// The list marker starting a line and the number of bytes up to the item
+/// text: `- `, `* `, `+ `, `1. `, `1) `, `(1) ` or `#. `.
+fn marker(line: &str) -> Option<(Marker, usize)> {
+    let text = line.trim_start();
+    let (marker, len) = match text.chars().next()? {
+        c @ ('-' | '*' | '+') => (Marker::Bullet(c), 1),
+        _ => {
+            let inner = text.strip_prefix('(').unwrap_or(text);
+            let digits = inner.len() - inner.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let number = match &inner[..digits] {
+                "" if inner.starts_with('#') => 1,
+                "" => return None,
+                digits => digits.parse().ok()?,
+            };
+            let digits = digits.max(1);
+            let close = match inner[digits..].chars().next()? {
+                '.' if inner.len() == text.len() => 1,
+                ')' => 1,
+                _ => return None,
+            };
+            (
+                Marker::Ordered(number),
+                text.len() - inner.len() + digits + close,
+            )
+        }
+    };
+    let rest = &text[len..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let spaces = rest.len() - rest.trim_start_matches(' ').len();
+    Some((marker, len + spaces))
+}
+
+/// Whether a line starts a list item.
+pub fn is_list_item(line: &str) -> bool {
+    marker(line).is_some()
+}
+
+/// End of the lines from `from` that are blank or indented by at least
+/// `min_indent`, trailing blank lines excluded.
+fn block_end(lines: &[String], from: usize, min_indent: usize) -> usize {
+    let mut end = from;
+    for (j, line) in lines.iter().enumerate().skip(from) {
+        if is_blank(line) {
+            continue;
+        }
+        if leading_spaces(line) < min_indent {
+            break;
+        }
+        end = j + 1;
+    }
+    end
+}
+
+fn next_non_blank(lines: &[String], from: usize) -> usize {
+    (from..lines.len())
+        .find(|&j| !is_blank(&lines[j]))
+        .unwrap_or(lines.len())
+}
+
+fn nested(lines: &[String], spans: &[Span], from: usize, to: usize) -> Vec<Block> {
+    parse_blocks(
+        &dedent(&lines[from..to]),
+        spans.get(from..to).unwrap_or(&[]),
+    )
+}
+
+/// The list starting at the line `i`, and the index of the line after it.
+fn list(lines: &[String], spans: &[Span], i: usize, level: usize) -> (Block, usize) {
+    let (first, _) = marker(&lines[i]).expect("a list starts with a marker");
+    let mut items = vec![];
+    let mut end = i;
+    loop {
+        let k = next_non_blank(lines, end);
+        if k == lines.len() || leading_spaces(&lines[k]) != level {
+            break;
+        }
+        let Some((marker, width)) = marker(&lines[k]) else {
+            break;
+        };
+        if !marker.continues(first) {
+            break;
+        }
+        let item_end = block_end(lines, k + 1, level + 1);
+        let continuation = lines[k + 1..item_end]
+            .iter()
+            .filter(|line| !is_blank(line))
+            .map(|line| leading_spaces(line))
+            .min();
+        let text = lines[k].trim_start();
+        let column = continuation.map_or(level + width, |col| col.min(level + width));
+        let mut item = vec![format!("{}{}", " ".repeat(column), &text[width..])];
+        item.extend(lines[k + 1..item_end].iter().cloned());
+        items.push(parse_blocks(
+            &dedent(&item),
+            spans.get(k..item_end).unwrap_or(&[]),
+        ));
+        end = item_end;
+    }
+    let kind = match first {
+        Marker::Bullet(_) => BlockKind::BulletList(items),
+        Marker::Ordered(start) => BlockKind::OrderedList { start, items },
+    };
+    let span = cover(spans, i, end - 1);
+    (Block { kind, span }, end)
+}
+
+/// The definition list starting at the line `i`, and the index of the line
+/// after it.
+fn definition_list(lines: &[String], spans: &[Span], i: usize, level: usize) -> (Block, usize) {
+    let mut items = vec![];
+    let mut end = i;
+    loop {
+        let k = next_non_blank(lines, end);
+        let is_term = k + 1 < lines.len()
+            && leading_spaces(&lines[k]) == level
+            && marker(&lines[k]).is_none()
+            && !is_blank(&lines[k + 1])
+            && leading_spaces(&lines[k + 1]) > level;
+        if !is_term {
+            break;
+        }
+        let item_end = block_end(lines, k + 1, level + 1);
+        items.push(DefinitionItem {
+            term: parse_inline(lines[k].trim()),
+            definition: nested(lines, spans, k + 1, item_end),
+        });
+        end = item_end;
+    }
+    let span = cover(spans, i, end - 1);
+    (
+        Block {
+            kind: BlockKind::DefinitionList(items),
+            span,
+        },
+        end,
+    )
+}
+
+/// Parse the lines of a description into paragraphs, bullet, enumerated and
+/// definition lists, literal blocks and block quotes, `spans` holding one
+/// span per line. Their indentation is expanded to spaces when lowering, so
+/// the tab size used for parsing is taken into account already.
+///
+/// Lists may follow a paragraph without a blank line in between, as in
+/// Markdown.
+pub fn parse_blocks(lines: &[String], spans: &[Span]) -> Vec<Block> {
+    let level = lines
+        .iter()
+        .filter(|line| !is_blank(line))
+        .map(|line| leading_spaces(line))
+        .min()
+        .unwrap_or(0);
+    let mut blocks = vec![];
+    // Whether the last paragraph ended with `::`.
+    let mut literal = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        if is_blank(line) {
+            i += 1;
+            continue;
+        }
+        if leading_spaces(line) > level {
+            let end = block_end(lines, i, level + 1);
+            let kind = if literal {
+                BlockKind::LiteralBlock(dedent(&lines[i..end]).join("\n"))
+            } else {
+                BlockKind::BlockQuote(nested(lines, spans, i, end))
+            };
+            blocks.push(Block {
+                kind,
+                span: cover(spans, i, end - 1),
+            });
+            literal = false;
+            i = end;
+            continue;
+        }
+        literal = false;
+        if marker(line).is_some() {
+            let (block, end) = list(lines, spans, i, level);
+            blocks.push(block);
+            i = end;
+            continue;
+        }
+        let is_term = lines
+            .get(i + 1)
+            .is_some_and(|next| !is_blank(next) && leading_spaces(next) > level)
+            && !line.trim_end().ends_with("::");
+        if is_term {
+            let (block, end) = definition_list(lines, spans, i, level);
+            blocks.push(block);
+            i = end;
+            continue;
+        }
+        let mut end = i + 1;
+        while end < lines.len()
+            && !is_blank(&lines[end])
+            && leading_spaces(&lines[end]) == level
+            && marker(&lines[end]).is_none()
+        {
+            end += 1;
+        }
+        let mut text = dedent(&lines[i..end]).join("\n");
+        if let Some(head) = text.strip_suffix("::") {
+            literal = true;
+            text = if head.ends_with(char::is_whitespace) {
+                head.trim_end().to_string()
+            } else {
+                format!("{}:", head)
+            };
+        }
+        if !text.is_empty() {
+            blocks.push(Block {
+                kind: BlockKind::Paragraph(parse_inline(&text)),
+                span: cover(spans, i, end - 1),
+            });
+        }
+        i = end;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Inline;
+
+    fn lines(doc: &str) -> Vec<String> {
+        doc.split('\n').map(str::to_string).collect()
+    }
+
+    fn block(kind: BlockKind) -> Block {
+        Block {
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    fn paragraph(text: &str) -> Block {
+        block(BlockKind::Paragraph(vec![Inline::Text(text.to_string())]))
+    }
+
+    #[test]
+    fn test_paragraphs_and_literal_blocks() {
+        let doc = "First paragraph
+on two lines.
+
+For example::
+
+    x = 1
+
+After.
+
+  Quoted.";
+        assert_eq!(
+            parse_blocks(&lines(doc), &[]),
+            [
+                paragraph("First paragraph\non two lines."),
+                paragraph("For example:"),
+                block(BlockKind::LiteralBlock("x = 1".to_string())),
+                paragraph("After."),
+                block(BlockKind::BlockQuote(vec![paragraph("Quoted.")])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let doc = "One of:
+- a, the first
+  letter.
+
+  Really.
+- b
+1. one
+2) two
+
+(3) three";
+        assert_eq!(
+            parse_blocks(&lines(doc), &[]),
+            [
+                paragraph("One of:"),
+                block(BlockKind::BulletList(vec![
+                    vec![paragraph("a, the first\nletter."), paragraph("Really."),],
+                    vec![paragraph("b")],
+                ])),
+                block(BlockKind::OrderedList {
+                    start: 1,
+                    items: vec![
+                        vec![paragraph("one")],
+                        vec![paragraph("two")],
+                        vec![paragraph("three")],
+                    ],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_list() {
+        let doc = "mean
+    The average.
+sum
+    The total.";
+        assert_eq!(
+            parse_blocks(&lines(doc), &[]),
+            [block(BlockKind::DefinitionList(vec![
+                DefinitionItem {
+                    term: vec![Inline::Text("mean".to_string())],
+                    definition: vec![paragraph("The average.")],
+                },
+                DefinitionItem {
+                    term: vec![Inline::Text("sum".to_string())],
+                    definition: vec![paragraph("The total.")],
+                },
+            ]))]
+        );
+    }
+
+    #[test]
+    fn test_not_a_list() {
+        assert_eq!(marker("-1 is the default"), None);
+        assert_eq!(marker("3.5 times"), None);
+        assert_eq!(
+            parse_blocks(&lines("-1 means all.\n2.5 is fine."), &[]),
+            [paragraph("-1 means all.\n2.5 is fine.")]
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use crate::context::Context;
 use crate::cursor::Cursor;
 use crate::error::ParseError;
-use crate::indent::is_indent_char;
+use crate::indent::{calc_indent_size, indent_offset, is_indent_char, leading_spaces};
+use crate::parser::cst::{CstNode, SyntaxKind};
+use crate::span::{LineIndex, Span};
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
@@ -9,50 +11,176 @@ pub fn identifier(input: &mut Cursor, _ctx: &mut Context) -> ParseResult<String>
     let start_pos = input.pos;
     match input.peek() {
         Some(_c @ ('A'..='Z' | 'a'..='z' | '0'..='9' | '_')) => input.skip_n(1),
-        Some(c) => {
-            return Err(ParseError::invalid_value(
-                start_pos,
-                format!("Invalid identifier start: {}", c),
-            ))
-        }
-        None => return Err(ParseError::unexpected_end(start_pos)),
+        Some(c) => return Err(ParseError::invalid_identifier(start_pos, c)),
+        None => return Err(ParseError::unexpected_end(start_pos, &["identifier"])),
     };
     let _ = input.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
     Ok(input.input[start_pos..input.pos].to_string())
 }
 
-pub fn next_line(input: &mut Cursor, _ctx: &mut Context) -> ParseResult<String> {
-    let line = input.take_until(|c| c == '\n').to_string();
+/// A dotted path such as `requests.HTTPError`.
+pub fn dotted_name(input: &mut Cursor, ctx: &mut Context) -> ParseResult<String> {
+    let start_pos = input.pos;
+    identifier(input, ctx)?;
+    while input.peek() == Some('.') {
+        input.skip_n(1);
+        identifier(input, ctx)?;
+    }
+    Ok(input.input[start_pos..input.pos].to_string())
+}
+
+/// Consume the rest of the current line as a node of the given kind, its
+/// line break being `\n`, `\r\n` or a lone `\r`.
+pub fn line(input: &mut Cursor, _ctx: &mut Context, kind: SyntaxKind) -> ParseResult<CstNode> {
+    let mut node = CstNode::new(kind);
+    let start = input.pos;
+    input.eat_indent();
+    node.push_since(SyntaxKind::Indent, input, start);
+    let start = input.pos;
+    let text = input.take_until(|c| c == '\n' || c == '\r');
+    let content_len = text.trim_end_matches(is_indent_char).len();
+    node.push_token(SyntaxKind::Text, &text[..content_len], start);
+    node.push_token(
+        SyntaxKind::Whitespace,
+        &text[content_len..],
+        start + content_len,
+    );
+    let start = input.pos;
     match input.peek() {
         Some('\n') => input.skip_n(1),
+        Some('\r') => {
+            input.skip_n(1);
+            if input.peek() == Some('\n') {
+                input.skip_n(1);
+            }
+        }
         None => {}
-        Some(_) => {
-            return Err(ParseError::invalid_value(
-                input.pos,
-                "Expected newline".to_string(),
-            ))
+        Some(c) => return Err(ParseError::unexpected_token(input.pos, &["newline"], c)),
+    }
+    node.push_since(SyntaxKind::Newline, input, start);
+    Ok(node)
+}
+
+pub fn blank_lines(input: &mut Cursor, node: &mut CstNode) {
+    let start = input.pos;
+    input.eat_empty_lines();
+    node.push_since(SyntaxKind::BlankLines, input, start);
+}
+
+/// Width of the indentation of the next non-blank line, `None` if there is
+/// none.
+pub fn next_indent(input: &Cursor, tab_size: usize) -> Option<usize> {
+    input.input[input.pos..]
+        .split(['\n', '\r'])
+        .find(|line| !line.chars().all(is_indent_char))
+        .map(|line| calc_indent_size(line, tab_size))
+}
+
+/// Collect the lines indented deeper than the context, blank lines included.
+pub fn indented_block(input: &mut Cursor, ctx: &mut Context) -> ParseResult<CstNode> {
+    let mut block = CstNode::new(SyntaxKind::Block);
+    while !input.eof() {
+        let next = input.input[input.pos..]
+            .split(['\n', '\r'])
+            .next()
+            .unwrap_or("");
+        let indent_size = calc_indent_size(next, ctx.options.tab_size);
+        if !next.chars().all(is_indent_char) && indent_size <= ctx.indent {
+            break;
         }
+        block.push_node(line(input, ctx, SyntaxKind::Line)?);
     }
-    Ok(line)
+    Ok(block)
 }
 
-pub fn indented_paragraph(input: &mut Cursor, ctx: &mut Context) -> ParseResult<Vec<String>> {
-    let lines = input.take_until_dedent(ctx.indent);
-    let min_indent_size_in_lines = lines
+/// Remove the indentation common to the non-blank lines, blank lines become
+/// empty. The indentation is expanded to spaces already.
+pub fn dedent<S: AsRef<str>>(lines: &[S]) -> Vec<String> {
+    let margin = lines
         .iter()
-        .filter(|s| !s.is_empty())
-        .map(|s| s.chars().take_while(|s| is_indent_char(*s)).count())
+        .map(AsRef::as_ref)
+        .filter(|line| !line.chars().all(is_indent_char))
+        .map(leading_spaces)
         .min()
         .unwrap_or(0);
-    let trimed_lines: Vec<String> = lines
+    lines
         .iter()
-        .map(|s| {
-            if s.is_empty() {
-                "".to_string()
+        .map(AsRef::as_ref)
+        .map(|line| {
+            if line.chars().all(is_indent_char) {
+                String::new()
             } else {
-                s.chars().skip(min_indent_size_in_lines).collect()
+                line[margin..].to_string()
             }
         })
+        .collect()
+}
+
+/// Span of a line without its line break and first `skip` bytes of indentation.
+pub fn line_span(line: &CstNode, skip: usize, index: &LineIndex) -> Option<Span> {
+    let tokens = line.descendant_tokens();
+    let first = tokens.first()?;
+    let start = match line.token(SyntaxKind::Indent) {
+        Some(indent) => indent.offset + skip.min(indent.text.len()),
+        None => first.offset,
+    };
+    let end = tokens
+        .iter()
+        .rev()
+        .find(|token| token.kind != SyntaxKind::Newline)
+        .map_or(start, |token| token.end().max(start));
+    Some(index.span(start, end))
+}
+
+/// The span from the line at `first` to the one at `last`, `spans` holding
+/// one span per line.
+pub fn cover(spans: &[Span], first: usize, last: usize) -> Span {
+    match (spans.get(first), spans.get(last)) {
+        (Some(first), Some(last)) => first.to(last),
+        _ => Span::default(),
+    }
+}
+
+/// Dedent the lines of a block, dropping its trailing blank lines.
+///
+/// What is left of the indentation is expanded to spaces.
+pub fn lower_block(
+    block: &CstNode,
+    index: &LineIndex,
+    tab_size: usize,
+) -> (Vec<String>, Vec<Span>) {
+    let lines: Vec<(&str, &CstNode, String)> = block
+        .nodes()
+        .map(|line| {
+            let indent = line
+                .token(SyntaxKind::Indent)
+                .map_or("", |token| token.text.as_str());
+            (indent, line, line.content())
+        })
         .collect();
-    Ok(trimed_lines)
+    let min_indent_size_in_lines = lines
+        .iter()
+        .filter(|(_, _, s)| !s.chars().all(is_indent_char))
+        .map(|(indent, _, _)| calc_indent_size(indent, tab_size))
+        .min()
+        .unwrap_or(0);
+    let mut trimed_lines: Vec<String> = vec![];
+    let mut spans: Vec<Span> = vec![];
+    for (indent, line, s) in &lines {
+        if s.chars().all(is_indent_char) {
+            trimed_lines.push("".to_string());
+            let start = line.descendant_tokens()[0].offset;
+            spans.push(index.span(start, start));
+        } else {
+            let extra = calc_indent_size(indent, tab_size) - min_indent_size_in_lines;
+            trimed_lines.push(" ".repeat(extra) + s.trim_start_matches(is_indent_char));
+            let skip = indent_offset(indent, min_indent_size_in_lines, tab_size);
+            spans.push(line_span(line, skip, index).unwrap());
+        }
+    }
+    while trimed_lines.last().is_some_and(|s| s.is_empty()) {
+        trimed_lines.pop();
+        spans.pop();
+    }
+    (trimed_lines, spans)
 }
@@ -0,0 +1,334 @@
+use crate::cursor::Cursor;
+use crate::span::{LineIndex, Span};
+use std::fmt;
+
+/// The kind of a node or token of the syntax tree.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SyntaxKind {
+    // nodes
+    /// The whole docstring: lines and sections.
+    Docstring,
+    /// A heading and its body.
+    Section,
+    /// The line introducing a section.
+    Heading,
+    /// The entries of an `Args` or `Raises` section.
+    ArgList,
+    /// An entry of an `Args` or `Raises` section.
+    Argument,
+    /// Lines indented deeper than the enclosing ones.
+    Block,
+    /// A line, its line break included.
+    Line,
+    /// Input skipped while recovering from an error.
+    Error,
+    // tokens
+    /// Leading spaces and tabs.
+    Indent,
+    /// Spaces and tabs elsewhere.
+    Whitespace,
+    /// Blank lines between entries.
+    BlankLines,
+    /// A line break.
+    Newline,
+    /// The content of a line.
+    Text,
+    /// The name of an entry.
+    Name,
+    /// `(` opening the type of an entry.
+    LParen,
+    /// The type of an entry.
+    Type,
+    /// `)` closing the type of an entry.
+    RParen,
+    /// `:` ending the head of an entry.
+    Colon,
+}
+
+impl SyntaxKind {
+    /// Whether tokens of this kind are whitespace.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Indent
+                | SyntaxKind::Whitespace
+                | SyntaxKind::BlankLines
+                | SyntaxKind::Newline
+        )
+    }
+}
+
+/// A leaf of the syntax tree.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CstToken {
+    /// What the token is.
+    pub kind: SyntaxKind,
+    /// The exact text of the token.
+    pub text: String,
+    /// Byte offset of the token in the parsed input.
+    pub offset: usize,
+}
+
+impl CstToken {
+    /// Byte offset of the end of the token.
+    pub fn end(&self) -> usize {
+        self.offset + self.text.len()
+    }
+
+    /// Span of the token in the text `index` was built from.
+    pub fn span(&self, index: &LineIndex) -> Span {
+        index.span(self.offset, self.end())
+    }
+}
+
+/// A child of a [`CstNode`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum CstElement {
+    /// An inner node.
+    Node(CstNode),
+    /// A token.
+    Token(CstToken),
+}
+
+/// A node of the lossless syntax tree.
+///
+/// Every byte of the input belongs to exactly one token, so printing the tree
+/// gives back the original text, including blank lines and trailing spaces.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CstNode {
+    /// What the node is.
+    pub kind: SyntaxKind,
+    /// Nodes and tokens, in source order.
+    pub children: Vec<CstElement>,
+}
+
+impl CstNode {
+    /// A node without children.
+    pub fn new(kind: SyntaxKind) -> CstNode {
+        CstNode {
+            kind,
+            children: vec![],
+        }
+    }
+
+    /// Append a child node.
+    pub fn push_node(&mut self, node: CstNode) {
+        self.children.push(CstElement::Node(node));
+    }
+
+    /// Append a token, unless `text` is empty.
+    pub fn push_token(&mut self, kind: SyntaxKind, text: &str, offset: usize) {
+        if !text.is_empty() {
+            self.children.push(CstElement::Token(CstToken {
+                kind,
+                text: text.to_string(),
+                offset,
+            }));
+        }
+    }
+
+    /// Push everything the cursor consumed since `start` as a single token.
+    pub(crate) fn push_since(&mut self, kind: SyntaxKind, input: &Cursor, start: usize) {
+        self.push_token(kind, &input.input[start..input.pos], start);
+    }
+
+    /// Direct child nodes of this node.
+    pub fn nodes(&self) -> impl Iterator<Item = &CstNode> {
+        self.children.iter().filter_map(|child| match child {
+            CstElement::Node(node) => Some(node),
+            CstElement::Token(_) => None,
+        })
+    }
+
+    /// Direct child nodes of this node, for editing.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut CstNode> {
+        self.children.iter_mut().filter_map(|child| match child {
+            CstElement::Node(node) => Some(node),
+            CstElement::Token(_) => None,
+        })
+    }
+
+    /// Direct child tokens of this node.
+    pub fn tokens(&self) -> impl Iterator<Item = &CstToken> {
+        self.children.iter().filter_map(|child| match child {
+            CstElement::Token(token) => Some(token),
+            CstElement::Node(_) => None,
+        })
+    }
+
+    /// Direct child tokens of this node, for editing.
+    pub fn tokens_mut(&mut self) -> impl Iterator<Item = &mut CstToken> {
+        self.children.iter_mut().filter_map(|child| match child {
+            CstElement::Token(token) => Some(token),
+            CstElement::Node(_) => None,
+        })
+    }
+
+    /// The first child node of the given kind.
+    pub fn node(&self, kind: SyntaxKind) -> Option<&CstNode> {
+        self.nodes().find(|node| node.kind == kind)
+    }
+
+    /// The first child token of the given kind.
+    pub fn token(&self, kind: SyntaxKind) -> Option<&CstToken> {
+        self.tokens().find(|token| token.kind == kind)
+    }
+
+    /// The first child token of the given kind, for editing.
+    pub fn token_mut(&mut self, kind: SyntaxKind) -> Option<&mut CstToken> {
+        self.tokens_mut().find(|token| token.kind == kind)
+    }
+
+    /// All tokens under this node, in source order.
+    pub fn descendant_tokens(&self) -> Vec<&CstToken> {
+        let mut res = vec![];
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => res.extend(node.descendant_tokens()),
+                CstElement::Token(token) => res.push(token),
+            }
+        }
+        res
+    }
+
+    /// Span from the first to the last non-trivia token of this node.
+    pub fn span(&self, index: &LineIndex) -> Option<Span> {
+        let tokens = self.descendant_tokens();
+        let significant = |token: &&&CstToken| !token.kind.is_trivia();
+        let first = tokens.iter().find(significant)?;
+        let last = tokens.iter().rfind(significant)?;
+        Some(index.span(first.offset, last.end()))
+    }
+
+    /// Give the tokens the offsets they have in the printed tree, which
+    /// editing token texts makes stale.
+    pub(crate) fn renumber(&mut self, offset: &mut usize) {
+        for child in &mut self.children {
+            match child {
+                CstElement::Node(node) => node.renumber(offset),
+                CstElement::Token(token) => {
+                    token.offset = *offset;
+                    *offset += token.text.len();
+                }
+            }
+        }
+    }
+
+    /// Text of this node, leaving out line break tokens.
+    pub fn content(&self) -> String {
+        self.descendant_tokens()
+            .iter()
+            .filter(|token| token.kind != SyntaxKind::Newline)
+            .map(|token| token.text.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.descendant_tokens() {
+            f.write_str(&token.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{ParseOptions, Strictness};
+    use crate::schema::{Argument, DocstringParagraph, WithoutSpans};
+    use crate::{lower_cst, parse_cst_with};
+
+    fn parse_cst(doc: &str) -> CstNode {
+        crate::parse_cst(doc).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let docs = [
+            "",
+            "Summary line.",
+            "Summary line.  \n\n  Description\t\n",
+            "Parameters:  \n\n    arg1  ( int ) :  Description of arg1 \n\n\n    arg2:\n        multi line\n        \n        description  \n  \nReturns:\n    value\n\n",
+            "Args:\n  arg1 (int):\tDescription of arg1\n      line 2\nExamples:\n\n    >>> 1 + 1\n    2\n",
+        ];
+        for doc in docs {
+            assert_eq!(parse_cst(doc).to_string(), doc);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_with_errors() {
+        let doc = "Args:\n    arg1 int: Description\n        more\n    arg2: Description\n";
+        assert!(crate::parse_cst(doc).is_err());
+        let options = ParseOptions {
+            strictness: Strictness::Lenient,
+            ..ParseOptions::default()
+        };
+        let cst = parse_cst_with(doc, &options).unwrap();
+        let arg_list = cst
+            .nodes()
+            .next()
+            .unwrap()
+            .node(SyntaxKind::ArgList)
+            .unwrap();
+        assert_eq!(
+            arg_list.nodes().map(|node| node.kind).collect::<Vec<_>>(),
+            [SyntaxKind::Error, SyntaxKind::Argument]
+        );
+        assert_eq!(cst.to_string(), doc);
+    }
+
+    #[test]
+    fn test_lower_matches_ast() {
+        let doc = "Summary.\nArgs:\n    arg1 (int): Description of arg1\n          indented\n\n        more\n";
+        assert_eq!(
+            lower_cst(&parse_cst(doc), &ParseOptions::default())
+                .into_iter()
+                .map(|section| section.paragraph.without_spans())
+                .collect::<Vec<_>>(),
+            vec![
+                DocstringParagraph::Raw("Summary.".to_string()),
+                DocstringParagraph::Args(vec![Argument {
+                    name: "arg1".to_string(),
+                    r#type: Some("int".to_string()),
+                    desc: vec![
+                        "Description of arg1".to_string(),
+                        "  indented".to_string(),
+                        "".to_string(),
+                        "more".to_string()
+                    ],
+                    ..Default::default()
+                }])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit_argument() {
+        let doc = "Summary.  \n\nArgs:\n    arg1 (int):  Description of arg1\n    arg2  (str): Description of arg2\n";
+        let mut cst = parse_cst(doc);
+        let section = cst
+            .nodes_mut()
+            .find(|node| node.kind == SyntaxKind::Section)
+            .unwrap();
+        let arg_list = section
+            .nodes_mut()
+            .find(|node| node.kind == SyntaxKind::ArgList)
+            .unwrap();
+        let arg2 = arg_list.nodes_mut().nth(1).unwrap();
+        arg2.token_mut(SyntaxKind::Type).unwrap().text = "bytes".to_string();
+        assert_eq!(
+            cst.to_string(),
+            "Summary.  \n\nArgs:\n    arg1 (int):  Description of arg1\n    arg2  (bytes): Description of arg2\n"
+        );
+        let docstring = lower_cst(&cst, &ParseOptions::default());
+        let arg2 = docstring.param("arg2").unwrap();
+        assert_eq!(arg2.r#type.as_deref(), Some("bytes"));
+        assert_eq!(
+            arg2.desc_spans[0].start,
+            cst.to_string().rfind("Desc").unwrap()
+        );
+    }
+}
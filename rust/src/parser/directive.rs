@@ -0,0 +1,215 @@
+use crate::indent::leading_spaces;
+use crate::parser::common::{cover, dedent};
+use crate::schema::{Directive, DirectiveKind};
+use crate::span::Span;
+
+/// A reStructuredText directive found in the lines of a section:
+///
+/// ```text
+/// .. name:: argument
+///    :option: value
+///
+///    content
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct RawDirective {
+    pub name: String,
+    pub argument: String,
+    /// The field list right after the heading, flags have an empty value.
+    pub options: Vec<(String, String)>,
+    /// The indented body after the options, dedented, without the blank
+    /// lines around it.
+    pub content: Vec<String>,
+    /// Number of lines taken by the directive, heading included.
+    pub len: usize,
+}
+
+/// `(name, value)` of a `:name: value` field.
+fn field(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.trim().strip_prefix(':')?.split_once(':')?;
+    if name.is_empty() || name.starts_with(' ') {
+        return None;
+    }
+    Some((name.to_string(), value.trim().to_string()))
+}
+
+/// Parse the directive starting at the first of `lines`, if any.
+pub fn directive_at(lines: &[String]) -> Option<RawDirective> {
+    let heading = lines.first()?;
+    let indent = leading_spaces(heading);
+    let (name, argument) = heading[indent..].strip_prefix(".. ")?.split_once("::")?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    if !argument.is_empty() && !argument.starts_with(' ') {
+        return None;
+    }
+
+    let mut body: Vec<&str> = lines[1..]
+        .iter()
+        .map(String::as_str)
+        .take_while(|line| line.trim().is_empty() || leading_spaces(line) > indent)
+        .collect();
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    let len = 1 + body.len();
+    let mut body = body.into_iter().peekable();
+    let mut options = vec![];
+    while let Some(option) = body.peek().and_then(|line| field(line)) {
+        options.push(option);
+        body.next();
+    }
+    let content: Vec<&str> = body.skip_while(|line| line.trim().is_empty()).collect();
+    Some(RawDirective {
+        name: name.to_string(),
+        argument: argument.trim().to_string(),
+        options,
+        content: dedent(&content),
+        len,
+    })
+}
+
+/// Split the text of the heading line off a version argument.
+fn version_and_text(argument: &str) -> (String, Option<String>) {
+    match argument.split_once(char::is_whitespace) {
+        Some((version, text)) => (version.to_string(), Some(text.trim().to_string())),
+        None => (argument.to_string(), None),
+    }
+}
+
+/// Give a directive its typed form.
+pub fn directive(raw: RawDirective, span: Span) -> Directive {
+    let argument = Some(raw.argument.clone()).filter(|argument| !argument.is_empty());
+    let (kind, leading) = match raw.name.as_str() {
+        "note" => (DirectiveKind::Note, argument),
+        "warning" => (DirectiveKind::Warning, argument),
+        "math" => (DirectiveKind::Math, argument),
+        "deprecated" | "versionadded" | "versionchanged" => {
+            let (version, text) = version_and_text(&raw.argument);
+            let kind = match raw.name.as_str() {
+                "deprecated" => DirectiveKind::Deprecated { version },
+                "versionadded" => DirectiveKind::VersionAdded { version },
+                _ => DirectiveKind::VersionChanged { version },
+            };
+            (kind, text)
+        }
+        "image" => (
+            DirectiveKind::Image {
+                uri: raw.argument.clone(),
+            },
+            None,
+        ),
+        "code-block" | "code" | "sourcecode" => {
+            (DirectiveKind::CodeBlock { language: argument }, None)
+        }
+        _ => (DirectiveKind::Generic, None),
+    };
+    Directive {
+        name: raw.name,
+        argument: raw.argument,
+        kind,
+        options: raw.options,
+        content: leading.into_iter().chain(raw.content).collect(),
+        span,
+    }
+}
+
+/// The directives of the lines of a description, `spans` holding one span
+/// per line. Directives nested in the content of another one are not
+/// reported.
+pub fn parse_directives(lines: &[String], spans: &[Span]) -> Vec<Directive> {
+    let mut directives = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(raw) = directive_at(&lines[i..]) else {
+            i += 1;
+            continue;
+        };
+        let span = cover(spans, i, i + raw.len - 1);
+        i += raw.len;
+        directives.push(directive(raw, span));
+    }
+    directives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(doc: &str) -> Vec<String> {
+        doc.split('\n').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_directive_at() {
+        let doc = "  .. code-block:: python
+     :name: example
+     :linenos:
+
+     def f():
+         pass
+
+  After.";
+        assert_eq!(
+            directive_at(&lines(doc)),
+            Some(RawDirective {
+                name: "code-block".to_string(),
+                argument: "python".to_string(),
+                options: vec![
+                    ("name".to_string(), "example".to_string()),
+                    ("linenos".to_string(), "".to_string()),
+                ],
+                content: vec!["def f():".to_string(), "    pass".to_string()],
+                len: 6,
+            })
+        );
+        assert_eq!(directive_at(&lines(".. note::")).unwrap().len, 1);
+        assert_eq!(directive_at(&lines(".. _target:")), None);
+        assert_eq!(directive_at(&lines("..  not a directive:: x")), None);
+    }
+
+    #[test]
+    fn test_parse_directives() {
+        let doc = "Some text.
+
+.. note:: Keep it short.
+   Really.
+
+.. deprecated:: 2.5 Use :func:`g` instead.
+.. versionadded:: 2.6
+.. image:: picture.png
+   :alt: A picture.
+.. custom:: argument
+
+   Body.";
+        let directives = parse_directives(&lines(doc), &[]);
+        let kinds: Vec<&DirectiveKind> = directives.iter().map(|d| &d.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &DirectiveKind::Note,
+                &DirectiveKind::Deprecated {
+                    version: "2.5".to_string()
+                },
+                &DirectiveKind::VersionAdded {
+                    version: "2.6".to_string()
+                },
+                &DirectiveKind::Image {
+                    uri: "picture.png".to_string()
+                },
+                &DirectiveKind::Generic,
+            ]
+        );
+        assert_eq!(directives[0].content, ["Keep it short.", "Really."]);
+        assert_eq!(directives[1].content, ["Use :func:`g` instead."]);
+        assert!(directives[2].content.is_empty());
+        assert_eq!(
+            directives[3].options,
+            [("alt".to_string(), "A picture.".to_string())]
+        );
+        assert_eq!(directives[4].name, "custom");
+        assert_eq!(directives[4].argument, "argument");
+        assert_eq!(directives[4].content, ["Body."]);
+    }
+}
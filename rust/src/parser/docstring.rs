@@ -1,101 +1,221 @@
 use crate::context::Context;
 use crate::cursor::Cursor;
+use crate::diagnostic::Diagnostic;
 use crate::guard::Guard;
-use crate::indent::{calc_indent_size, is_indent_char};
-use crate::parser::argument::parse_args;
-use crate::parser::common::{next_line, ParseResult};
-use crate::parser::plain_paragraph::parse_plain_paragraph;
-use crate::schema::{Argument, Docstring, DocstringParagraph};
-use crate::utils::cleandoc;
+use crate::indent::{calc_indent_size, expand_indent};
+use crate::options::ParseOptions;
+use crate::parser::argument::{arg_list_node, lower_arg_list};
+use crate::parser::common::{dotted_name, identifier, line, line_span, ParseResult};
+use crate::parser::cst::{CstNode, SyntaxKind};
+use crate::parser::plain_paragraph::{lower_plain_paragraph, plain_paragraph_node};
+use crate::schema::{Docstring, DocstringParagraph, Section};
+use crate::span::LineIndex;
+
+/// The sections recognized by their heading.
+///
+/// The first heading of each section is the one used when rendering.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DocstringTitle {
+    /// `Args:` or `Parameters:`.
     Args,
+    /// `Returns:`.
     Returns,
+    /// `Raises:`.
+    Raises,
+    /// `Examples:`.
+    Examples,
+    /// `Notes:`.
+    Notes,
+    /// `Warnings:`.
+    Warnings,
+    /// `Deprecated:`.
+    Deprecated,
 }
 
 impl DocstringTitle {
-    pub fn args_heads() -> [&'static str; 2] {
-        ["Args", "Parameters"]
-    }
-
-    pub fn returns_heads() -> [&'static str; 1] {
-        ["Returns"]
-    }
-
-    pub fn yields_heads() -> [&'static str; 1] {
-        ["Yields"]
+    /// All the headings of the section, the one used when rendering first.
+    pub fn heads(&self) -> &'static [&'static str] {
+        match self {
+            DocstringTitle::Args => &["Args", "Parameters"],
+            DocstringTitle::Returns => &["Returns"],
+            DocstringTitle::Raises => &["Raises"],
+            DocstringTitle::Examples => &["Examples"],
+            DocstringTitle::Notes => &["Notes"],
+            DocstringTitle::Warnings => &["Warnings"],
+            DocstringTitle::Deprecated => &["Deprecated"],
+        }
     }
 
-    pub fn raises_heads() -> [&'static str; 1] {
-        ["Raises"]
+    /// Find the section a line introduces with the default options, if any.
+    pub fn from_heading(line: &str) -> Option<DocstringTitle> {
+        ParseOptions::default().section_of(line)
     }
+}
 
-    pub fn examples_heads() -> [&'static str; 1] {
-        ["Examples"]
-    }
+/// The section name as written, without the trailing colon.
+fn heading_name(heading: &str) -> &str {
+    heading.trim().trim_end_matches(':')
+}
 
-    pub fn notes_heads() -> [&'static str; 1] {
-        ["Notes"]
-    }
+pub fn docstring_node(input: &mut Cursor, ctx: &mut Context) -> ParseResult<CstNode> {
+    let mut node = CstNode::new(SyntaxKind::Docstring);
+    loop {
+        if input.eof() {
+            break;
+        }
 
-    pub fn references_heads() -> [&'static str; 1] {
-        ["References"]
+        let line = line(input, ctx, SyntaxKind::Line)?;
+        let Some(title) = ctx.options.section_of(&line.content()) else {
+            node.push_node(line);
+            continue;
+        };
+        let mut section = CstNode::new(SyntaxKind::Section);
+        let indent = line.token(SyntaxKind::Indent).map_or(0, |token| {
+            calc_indent_size(&token.text, ctx.options.tab_size)
+        });
+        let heading = CstNode {
+            kind: SyntaxKind::Heading,
+            children: line.children,
+        };
+        let frame = format!("section `{}`", heading_name(&heading.content()));
+        section.push_node(heading);
+        let ctx_guard = ctx.guard(Context::new(indent, ctx.options));
+        let recovered = ctx.errors.len();
+        let body = match title {
+            DocstringTitle::Args => arg_list_node(input, ctx, identifier),
+            DocstringTitle::Raises => arg_list_node(input, ctx, dotted_name),
+            _ => plain_paragraph_node(input, ctx),
+        };
+        for err in ctx.errors.iter_mut().skip(recovered) {
+            *err = err.clone().with_context(frame.clone());
+        }
+        section.push_node(body.map_err(|err| err.with_context(frame))?);
+        ctx_guard.restore(ctx);
+        node.push_node(section);
     }
+    Ok(node)
+}
 
-    pub fn see_also_heads() -> [&'static str; 1] {
-        ["See Also"]
-    }
+pub fn lower_docstring(node: &CstNode, index: &LineIndex, options: &ParseOptions) -> Docstring {
+    node.nodes()
+        .flat_map(|child| {
+            let Some(heading) = child.node(SyntaxKind::Heading) else {
+                let span = line_span(child, 0, index).unwrap_or_default();
+                return vec![Section {
+                    paragraph: DocstringParagraph::Raw(expand_indent(
+                        &child.content(),
+                        options.tab_size,
+                    )),
+                    span,
+                    heading_span: None,
+                    line_spans: vec![span],
+                }];
+            };
+            // A heading the options do not enable, as in a tree parsed with
+            // other options, leaves its lines as they are.
+            let Some(title) = options.section_of(&heading.content()) else {
+                return raw_lines(child, index, options.tab_size);
+            };
+            let (lines, line_spans) = child
+                .node(SyntaxKind::Block)
+                .map_or((vec![], vec![]), |block| {
+                    lower_plain_paragraph(block, index, options.tab_size)
+                });
+            let paragraph = match title {
+                DocstringTitle::Args => DocstringParagraph::Args(
+                    child.node(SyntaxKind::ArgList).map_or(vec![], |arg_list| {
+                        lower_arg_list(arg_list, index, options.tab_size)
+                    }),
+                ),
+                DocstringTitle::Raises => DocstringParagraph::Raises(
+                    child.node(SyntaxKind::ArgList).map_or(vec![], |arg_list| {
+                        lower_arg_list(arg_list, index, options.tab_size)
+                    }),
+                ),
+                DocstringTitle::Examples => DocstringParagraph::Examples(lines),
+                DocstringTitle::Notes => DocstringParagraph::Note(lines.join("\n")),
+                DocstringTitle::Warnings => DocstringParagraph::Warning(lines.join("\n")),
+                DocstringTitle::Deprecated => DocstringParagraph::Deprecated(lines),
+                DocstringTitle::Returns => DocstringParagraph::Returns(lines),
+            };
+            vec![Section {
+                paragraph,
+                span: child.span(index).unwrap_or_default(),
+                heading_span: heading.span(index),
+                line_spans,
+            }]
+        })
+        .collect()
+}
 
-    pub fn warnings_heads() -> [&'static str; 1] {
-        ["Warnings"]
-    }
+/// One `Raw` section per line of a node.
+fn raw_lines(node: &CstNode, index: &LineIndex, tab_size: usize) -> Vec<Section> {
+    let tokens = node.descendant_tokens();
+    tokens
+        .split_inclusive(|token| token.kind == SyntaxKind::Newline)
+        .map(|line| {
+            let text: String = line
+                .iter()
+                .filter(|token| token.kind != SyntaxKind::Newline)
+                .map(|token| token.text.as_str())
+                .collect();
+            let start = line[0].offset;
+            let span = index.span(start, start + text.len());
+            Section {
+                paragraph: DocstringParagraph::Raw(expand_indent(&text, tab_size)),
+                span,
+                heading_span: None,
+                line_spans: vec![span],
+            }
+        })
+        .collect()
 }
 
 pub fn parse_docstring(input: &mut Cursor, ctx: &mut Context) -> ParseResult<Docstring> {
-    let mut docstring = vec![];
-    loop {
-        if input.eof() {
-            break;
-        }
+    let node = docstring_node(input, ctx)?;
+    Ok(lower_docstring(
+        &node,
+        &ctx.line_index(input.input),
+        ctx.options,
+    ))
+}
 
-        let line = next_line(input, ctx)?;
-        if DocstringTitle::args_heads()
-            .iter()
-            .any(|&head| line.starts_with(head))
-        {
-            let indent = line.chars().take_while(|c| is_indent_char(*c)).count();
-            let ctx_guard = ctx.guard(Context::new(indent));
-            docstring.push(DocstringParagraph::Args(parse_args(input, ctx)?));
-            ctx_guard.restore(ctx);
-        } else if DocstringTitle::returns_heads()
-            .iter()
-            .any(|&head| line.starts_with(head))
-        {
-            let indent = line.chars().take_while(|c| is_indent_char(*c)).count();
-            let ctx_guard = ctx.guard(Context::new(indent));
-            docstring.push(DocstringParagraph::Returns(parse_plain_paragraph(
-                input, ctx,
-            )?));
-            ctx_guard.restore(ctx);
-        } else if DocstringTitle::examples_heads()
-            .iter()
-            .any(|&head| line.starts_with(head))
-        {
-            let indent = line.chars().take_while(|c| is_indent_char(*c)).count();
-            let ctx_guard = ctx.guard(Context::new(indent));
-            docstring.push(DocstringParagraph::Examples(parse_plain_paragraph(
-                input, ctx,
-            )?));
-            ctx_guard.restore(ctx);
-        } else {
-            docstring.push(DocstringParagraph::Raw(line));
+/// Parse as much as possible, skipping malformed entries and reporting them.
+pub fn parse_docstring_recovering(
+    input: &mut Cursor,
+    ctx: &mut Context,
+) -> (Docstring, Vec<Diagnostic>) {
+    ctx.recover = true;
+    let index = ctx.line_index(input.input);
+    let docstring = match docstring_node(input, ctx) {
+        Ok(node) => lower_docstring(&node, &index, ctx.options),
+        Err(err) => {
+            ctx.errors.push(err);
+            Docstring::default()
         }
-    }
-    Ok(docstring)
+    };
+    let diagnostics = ctx
+        .errors
+        .drain(..)
+        .map(|err| {
+            Diagnostic::from_error(&err, &index).with_note("the entry was skipped".to_string())
+        })
+        .collect();
+    (docstring, diagnostics)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::{Argument, WithoutSpans};
+    use crate::utils::cleandoc;
+
+    fn paragraphs(docstring: Docstring) -> Vec<DocstringParagraph> {
+        docstring
+            .into_iter()
+            .map(|section| section.paragraph.without_spans())
+            .collect()
+    }
 
     #[test]
     fn test_parse_docstring() {
@@ -107,13 +227,15 @@ mod tests {
             None,
         );
         let mut cursor = Cursor::new(&doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
         assert_eq!(
-            parse_docstring(&mut cursor, &mut ctx),
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
             Ok(vec![DocstringParagraph::Args(vec![Argument {
                 name: "arg1".to_string(),
                 r#type: Some("int".to_string()),
-                desc: vec!["Description of arg1".to_string()]
+                desc: vec!["Description of arg1".to_string()],
+                ..Default::default()
             }])])
         );
     }
@@ -133,15 +255,17 @@ mod tests {
             None,
         );
         let mut cursor = Cursor::new(&doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
 
         assert_eq!(
-            parse_docstring(&mut cursor, &mut ctx),
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
             Ok(vec![DocstringParagraph::Args(vec![
                 Argument {
                     name: "arg1".to_string(),
                     r#type: Some("int".to_string()),
-                    desc: vec!["Description of arg1".to_string()]
+                    desc: vec!["Description of arg1".to_string()],
+                    ..Default::default()
                 },
                 Argument {
                     name: "arg2".to_string(),
@@ -150,7 +274,8 @@ mod tests {
                         "Description of arg2".to_string(),
                         "line 2 of arg2".to_string(),
                         "line 3 of arg2".to_string()
-                    ]
+                    ],
+                    ..Default::default()
                 },
                 Argument {
                     name: "arg3".to_string(),
@@ -158,7 +283,8 @@ mod tests {
                     desc: vec![
                         "Description of arg3".to_string(),
                         "line 2 of arg3".to_string()
-                    ]
+                    ],
+                    ..Default::default()
                 }
             ])])
         );
@@ -176,14 +302,16 @@ mod tests {
             None,
         );
         let mut cursor = Cursor::new(&doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
         assert_eq!(
-            parse_docstring(&mut cursor, &mut ctx),
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
             Ok(vec![
                 DocstringParagraph::Args(vec![Argument {
                     name: "arg1".to_string(),
                     r#type: Some("int".to_string()),
-                    desc: vec!["Description of arg1".to_string()]
+                    desc: vec!["Description of arg1".to_string()],
+                    ..Default::default()
                 }]),
                 DocstringParagraph::Returns(vec!["Description of return value".to_string()])
             ])
@@ -205,14 +333,16 @@ mod tests {
             None,
         );
         let mut cursor = Cursor::new(&doc);
-        let mut ctx = Context::new(4);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(4, &options);
         assert_eq!(
-            parse_docstring(&mut cursor, &mut ctx),
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
             Ok(vec![
                 DocstringParagraph::Args(vec![Argument {
                     name: "arg1".to_string(),
                     r#type: Some("int".to_string()),
-                    desc: vec!["Description of arg1".to_string()]
+                    desc: vec!["Description of arg1".to_string()],
+                    ..Default::default()
                 }]),
                 DocstringParagraph::Examples(vec![
                     "".to_string(),
@@ -224,4 +354,133 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn test_parse_docstring_spans() {
+        let doc = "Summary.\n\nReturns:\n    Description of return value\n";
+        let mut cursor = Cursor::new(doc);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        let docstring = parse_docstring(&mut cursor, &mut ctx).unwrap();
+        let index = LineIndex::new(doc);
+        assert_eq!(docstring.sections()[0].span, index.span(0, 8));
+        assert_eq!(docstring.sections()[1].span, index.span(9, 9));
+        assert_eq!(docstring.sections()[2].span, index.span(10, 50));
+        assert_eq!(
+            docstring.sections()[2].heading_span,
+            Some(index.span(10, 18))
+        );
+        assert_eq!(docstring.sections()[2].line_spans, vec![index.span(23, 50)]);
+        assert_eq!(docstring.sections()[2].line_spans[0].start_pos.line, 4);
+    }
+
+    #[test]
+    fn test_parse_docstring_expands_raw_indent() {
+        let doc = "Summary.\n\tMore.\nReturns:\n\tThe value.";
+        let mut cursor = Cursor::new(doc);
+        let options = ParseOptions {
+            tab_size: 8,
+            ..ParseOptions::default()
+        };
+        let mut ctx = Context::new(0, &options);
+        assert_eq!(
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
+            Ok(vec![
+                DocstringParagraph::Raw("Summary.".to_string()),
+                DocstringParagraph::Raw("        More.".to_string()),
+                DocstringParagraph::Returns(vec!["The value.".to_string()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lower_disabled_section() {
+        let doc = "Summary.\nNotes:\n    Mind\n\n    the x.\nReturns:\n    The value.";
+        let options = ParseOptions {
+            sections: vec![DocstringTitle::Notes, DocstringTitle::Returns],
+            ..ParseOptions::default()
+        };
+        let cst = crate::parse_cst_with(doc, &options).unwrap();
+        let docstring = crate::lower_cst(&cst, &ParseOptions::default());
+        assert_eq!(
+            docstring.sections()[2].span.start,
+            doc.find("    Mind").unwrap()
+        );
+        assert_eq!(
+            paragraphs(docstring),
+            vec![
+                DocstringParagraph::Raw("Summary.".to_string()),
+                DocstringParagraph::Raw("Notes:".to_string()),
+                DocstringParagraph::Raw("    Mind".to_string()),
+                DocstringParagraph::Raw("".to_string()),
+                DocstringParagraph::Raw("    the x.".to_string()),
+                DocstringParagraph::Returns(vec!["The value.".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_docstring_recovering() {
+        let doc = cleandoc(
+            "
+        Args:
+            arg1 (int) Description of arg1
+                more about arg1
+            arg2 (str): Description of arg2
+        Returns:
+            Description of return value
+        ",
+            None,
+        );
+        let mut cursor = Cursor::new(&doc);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        let (docstring, diagnostics) = parse_docstring_recovering(&mut cursor, &mut ctx);
+        assert_eq!(
+            paragraphs(docstring),
+            vec![
+                DocstringParagraph::Args(vec![Argument {
+                    name: "arg2".to_string(),
+                    r#type: Some("str".to_string()),
+                    desc: vec!["Description of arg2".to_string()],
+                    ..Default::default()
+                }]),
+                DocstringParagraph::Returns(vec!["Description of return value".to_string()])
+            ]
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "expected ':', found 'D'");
+        assert_eq!(diagnostics[0].code, Some("E003"));
+        assert_eq!(
+            diagnostics[0].notes[0],
+            "in argument `arg1` of section `Args`"
+        );
+        assert_eq!(diagnostics[0].span.start_pos.line, 2);
+        assert_eq!(diagnostics[0].span.start_pos.col, 15);
+    }
+
+    #[test]
+    fn test_parse_docstring_raises() {
+        let doc = "Raises:\n    ValueError: If the value is wrong.\n    requests.HTTPError: If the request fails.\n";
+        let mut cursor = Cursor::new(doc);
+        let options = ParseOptions::default();
+        let mut ctx = Context::new(0, &options);
+        assert_eq!(
+            parse_docstring(&mut cursor, &mut ctx).map(paragraphs),
+            Ok(vec![DocstringParagraph::Raises(vec![
+                Argument {
+                    name: "ValueError".to_string(),
+                    r#type: None,
+                    desc: vec!["If the value is wrong.".to_string()],
+                    ..Default::default()
+                },
+                Argument {
+                    name: "requests.HTTPError".to_string(),
+                    r#type: None,
+                    desc: vec!["If the request fails.".to_string()],
+                    ..Default::default()
+                }
+            ])])
+        );
+    }
 }
@@ -0,0 +1,246 @@
+use crate::indent::leading_spaces;
+use crate::parser::common::cover;
+use crate::parser::directive::directive_at;
+use crate::schema::{CodeBlock, Doctest, ExampleItem};
+use crate::span::Span;
+
+const PROMPT: &str = ">>>";
+const CONTINUATION: &str = "...";
+const FENCE: &str = "```";
+const CODE_BLOCK_DIRECTIVES: [&str; 3] = ["code-block", "code", "sourcecode"];
+
+/// The source of a line after `prompt`, `None` if the line does not start
+/// with it at `indent`.
+fn after_prompt<'a>(line: &'a str, indent: usize, prompt: &str) -> Option<&'a str> {
+    if leading_spaces(line) != indent {
+        return None;
+    }
+    match line[indent..].strip_prefix(prompt)? {
+        "" => Some(""),
+        rest => rest.strip_prefix(' '),
+    }
+}
+
+/// Directives of `# doctest: +SKIP, +ELLIPSIS` comments.
+fn directives(line: &str) -> Vec<String> {
+    line.match_indices('#')
+        .filter_map(|(i, _)| line[i + 1..].trim_start().strip_prefix("doctest:"))
+        .flat_map(|options| options.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|option| !option.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The code block starting at the first of `lines` and the number of lines
+/// it takes.
+fn code_block_at(lines: &[String]) -> Option<(CodeBlock, usize)> {
+    if let Some(directive) = directive_at(lines) {
+        if !CODE_BLOCK_DIRECTIVES.contains(&directive.name.as_str()) {
+            return None;
+        }
+        let block = CodeBlock {
+            language: Some(directive.argument).filter(|language| !language.is_empty()),
+            options: directive.options,
+            code: directive.content.join("\n"),
+            span: Span::default(),
+        };
+        return Some((block, directive.len));
+    }
+
+    let indent = leading_spaces(lines.first()?);
+    let info = lines[0][indent..].strip_prefix(FENCE)?;
+    if info.contains('`') {
+        return None;
+    }
+    let body: Vec<&str> = lines[1..]
+        .iter()
+        .take_while(|line| line.trim() != FENCE)
+        .map(|line| &line[indent.min(leading_spaces(line))..])
+        .collect();
+    let closed = lines.len() > body.len() + 1;
+    let block = CodeBlock {
+        language: info.split_whitespace().next().map(str::to_string),
+        options: vec![],
+        code: body.join("\n"),
+        span: Span::default(),
+    };
+    Some((block, body.len() + 1 + usize::from(closed)))
+}
+
+/// Split the lines of an `Examples` section into doctests, following the
+/// rules of Python's `doctest` module, code blocks and the text around them.
+///
+/// `spans` holds one span per line.
+pub fn parse_examples(lines: &[String], spans: &[Span]) -> Vec<ExampleItem> {
+    let flush = |items: &mut Vec<ExampleItem>, text: &mut Option<(usize, usize)>| {
+        if let Some((first, last)) = text.take() {
+            items.push(ExampleItem::Text {
+                lines: lines[first..=last].to_vec(),
+                span: cover(spans, first, last),
+            });
+        }
+    };
+    let mut items = vec![];
+    let mut text: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some((block, len)) = code_block_at(&lines[i..]) {
+            flush(&mut items, &mut text);
+            items.push(ExampleItem::CodeBlock(CodeBlock {
+                span: cover(spans, i, i + len - 1),
+                ..block
+            }));
+            i += len;
+            continue;
+        }
+        let indent = leading_spaces(&lines[i]);
+        let Some(first) = after_prompt(&lines[i], indent, PROMPT) else {
+            if !lines[i].trim().is_empty() {
+                text = Some((text.map_or(i, |(first, _)| first), i));
+            }
+            i += 1;
+            continue;
+        };
+        flush(&mut items, &mut text);
+        let start = i;
+        let mut source = vec![first.to_string()];
+        i += 1;
+        while let Some(line) = lines
+            .get(i)
+            .and_then(|line| after_prompt(line, indent, CONTINUATION))
+        {
+            source.push(line.to_string());
+            i += 1;
+        }
+        let mut want = vec![];
+        while let Some(line) = lines.get(i) {
+            if line.trim().is_empty() || after_prompt(line, indent, PROMPT).is_some() {
+                break;
+            }
+            let line = &line[indent.min(leading_spaces(line))..];
+            want.push(match line {
+                "<BLANKLINE>" => String::new(),
+                _ => line.to_string(),
+            });
+            i += 1;
+        }
+        items.push(ExampleItem::Doctest(Doctest {
+            options: source.iter().flat_map(|line| directives(line)).collect(),
+            source,
+            want,
+            span: cover(spans, start, i - 1),
+        }));
+    }
+    flush(&mut items, &mut text);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn examples(doc: &str) -> Vec<ExampleItem> {
+        let lines: Vec<String> = doc.split('\n').map(str::to_string).collect();
+        parse_examples(&lines, &[])
+    }
+
+    fn doctest(source: &[&str], want: &[&str], options: &[&str]) -> ExampleItem {
+        let strings = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect();
+        ExampleItem::Doctest(Doctest {
+            source: strings(source),
+            want: strings(want),
+            options: strings(options),
+            span: Span::default(),
+        })
+    }
+
+    fn text(lines: &[&str]) -> ExampleItem {
+        ExampleItem::Text {
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse_examples() {
+        let doc = "Add numbers:
+
+>>> add(1,
+...     2)
+3
+>>> print('a  b')  # doctest: +NORMALIZE_WHITESPACE, +ELLIPSIS
+a
+<BLANKLINE>
+b
+
+Nothing to show:
+
+    >>> x = 1  # doctest: +SKIP
+    >>>";
+        assert_eq!(
+            examples(doc),
+            vec![
+                text(&["Add numbers:"]),
+                doctest(&["add(1,", "    2)"], &["3"], &[]),
+                doctest(
+                    &["print('a  b')  # doctest: +NORMALIZE_WHITESPACE, +ELLIPSIS"],
+                    &["a", "", "b"],
+                    &["+NORMALIZE_WHITESPACE", "+ELLIPSIS"],
+                ),
+                text(&["Nothing to show:"]),
+                doctest(&["x = 1  # doctest: +SKIP"], &[], &["+SKIP"]),
+                doctest(&[""], &[], &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_a_prompt() {
+        assert_eq!(examples(">>>x\n>>>> y"), vec![text(&[">>>x", ">>>> y"])]);
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let doc = "Run it:
+
+.. code-block:: python
+   :name: code-example1
+
+   import os
+   print(os.sep)
+
+.. code::
+
+   pass
+```py
+x = 1
+```
+Done.";
+        let block = |language: Option<&str>, options: &[(&str, &str)], code: &str| {
+            ExampleItem::CodeBlock(CodeBlock {
+                language: language.map(str::to_string),
+                options: options
+                    .iter()
+                    .map(|&(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                code: code.to_string(),
+                span: Span::default(),
+            })
+        };
+        assert_eq!(
+            examples(doc),
+            vec![
+                text(&["Run it:"]),
+                block(
+                    Some("python"),
+                    &[("name", "code-example1")],
+                    "import os\nprint(os.sep)"
+                ),
+                block(None, &[], "pass"),
+                block(Some("py"), &[], "x = 1"),
+                text(&["Done."]),
+            ]
+        );
+        assert_eq!(examples("```\nopen"), vec![block(None, &[], "open")]);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::schema::Inline;
+
+/// Characters after which inline markup may start, besides whitespace.
+const OPENERS: &str = "'\"([{<-/:";
+
+/// Characters before which inline markup may end, besides whitespace.
+const CLOSERS: &str = "'\")]}>-/:.,;!?\\";
+
+fn can_start(text: &str, i: usize) -> bool {
+    text[..i]
+        .chars()
+        .next_back()
+        .is_none_or(|c| c.is_whitespace() || OPENERS.contains(c))
+}
+
+fn can_end(text: &str, i: usize) -> bool {
+    text[i..]
+        .chars()
+        .next()
+        .is_none_or(|c| c.is_whitespace() || CLOSERS.contains(c))
+}
+
+/// Byte offset of the end string `delim` closing markup whose content starts
+/// at `from`, and the first of `suffixes` following it. The content may not
+/// start or end with whitespace.
+fn closing<'s>(
+    text: &str,
+    from: usize,
+    delim: &str,
+    suffixes: &[&'s str],
+) -> Option<(usize, &'s str)> {
+    if text[from..].starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut at = from;
+    while let Some(found) = text[at..].find(delim) {
+        let end = at + found;
+        let after = end + delim.len();
+        if end > from && !text[..end].ends_with(char::is_whitespace) {
+            let suffix = suffixes.iter().find(|suffix| {
+                text[after..].starts_with(**suffix) && can_end(text, after + suffix.len())
+            });
+            if let Some(suffix) = suffix {
+                return Some((end, suffix));
+            }
+        }
+        at = end + delim.len().max(1);
+    }
+    None
+}
+
+/// Split `text <target>` into its parts, the target alone is its own text.
+fn link_parts(content: &str) -> (String, String) {
+    if let Some(head) = content.strip_suffix('>') {
+        if let Some((text, target)) = head.rsplit_once('<') {
+            let text = text.trim();
+            let text = if text.is_empty() { target } else { text };
+            return (text.to_string(), target.to_string());
+        }
+    }
+    (content.to_string(), content.to_string())
+}
+
+/// The markup starting at byte `i` and the offset past it.
+fn markup_at(text: &str, i: usize) -> Option<(Inline, usize)> {
+    let rest = &text[i..];
+    if rest.starts_with("``") {
+        let (end, _) = closing(text, i + 2, "``", &[""])?;
+        return Some((Inline::Literal(text[i + 2..end].to_string()), end + 2));
+    }
+    if rest.starts_with("**") {
+        let (end, _) = closing(text, i + 2, "**", &[""])?;
+        return Some((Inline::Strong(parse_inline(&text[i + 2..end])), end + 2));
+    }
+    if rest.starts_with('*') {
+        let (end, _) = closing(text, i + 1, "*", &[""])?;
+        return Some((Inline::Emphasis(parse_inline(&text[i + 1..end])), end + 1));
+    }
+    if let Some(role) = rest.strip_prefix(':') {
+        let name_len = role.find(|c: char| !(c.is_ascii_alphanumeric() || "-_.+:".contains(c)))?;
+        let name = role[..name_len].strip_suffix(':')?;
+        if name.is_empty() || !role[name_len..].starts_with('`') {
+            return None;
+        }
+        let start = i + 2 + name_len;
+        let (end, _) = closing(text, start, "`", &[""])?;
+        let role = Inline::Role {
+            name: name.to_string(),
+            text: text[start..end].to_string(),
+        };
+        return Some((role, end + 1));
+    }
+    if rest.starts_with('`') {
+        let (end, suffix) = closing(text, i + 1, "`", &["__", "_", ""])?;
+        let content = &text[i + 1..end];
+        let node = if suffix.is_empty() {
+            Inline::Literal(content.to_string())
+        } else {
+            let (text, target) = link_parts(content);
+            Inline::Link { text, target }
+        };
+        return Some((node, end + 1 + suffix.len()));
+    }
+    if rest.starts_with('[') {
+        let close = rest.find(']')?;
+        let label = &rest[1..close];
+        if label.is_empty() || label.contains('\n') {
+            return None;
+        }
+        let after = &rest[close + 1..];
+        if after.starts_with("_") && can_end(text, i + close + 2) {
+            let footnote = Inline::FootnoteRef(label.to_string());
+            return Some((footnote, i + close + 2));
+        }
+        let url = after.strip_prefix('(')?;
+        let url_end = url.find(')')?;
+        let link = Inline::Link {
+            text: label.to_string(),
+            target: url[..url_end].to_string(),
+        };
+        return Some((link, i + close + 2 + url_end + 1));
+    }
+    None
+}
+
+/// Parse the inline markup of reStructuredText and Markdown: literals,
+/// emphasis, strong emphasis, Sphinx roles, hyperlinks and footnote
+/// references. Markup that is not closed is kept as text.
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut nodes = vec![];
+    let mut plain = String::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let markup = if "`*:[".contains(c) && can_start(text, i) {
+            markup_at(text, i)
+        } else {
+            None
+        };
+        match markup {
+            Some((node, next)) => {
+                if !plain.is_empty() {
+                    nodes.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                nodes.push(node);
+                i = next;
+            }
+            None => {
+                plain.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    if !plain.is_empty() {
+        nodes.push(Inline::Text(plain));
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        assert_eq!(
+            parse_inline("Add ``x`` to *all* **the** `y`, see :func:`paddle.add`."),
+            [
+                text("Add "),
+                Inline::Literal("x".to_string()),
+                text(" to "),
+                Inline::Emphasis(vec![text("all")]),
+                text(" "),
+                Inline::Strong(vec![text("the")]),
+                text(" "),
+                Inline::Literal("y".to_string()),
+                text(", see "),
+                Inline::Role {
+                    name: "func".to_string(),
+                    text: "paddle.add".to_string()
+                },
+                text("."),
+            ]
+        );
+        assert_eq!(
+            parse_inline(":py:meth:`f` and :math:`x^2`"),
+            [
+                Inline::Role {
+                    name: "py:meth".to_string(),
+                    text: "f".to_string()
+                },
+                text(" and "),
+                Inline::Role {
+                    name: "math".to_string(),
+                    text: "x^2".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_links() {
+        let link = |text: &str, target: &str| Inline::Link {
+            text: text.to_string(),
+            target: target.to_string(),
+        };
+        assert_eq!(
+            parse_inline("`Paddle <https://paddle.org>`_, `docs`__ and [site](https://x.org)[1]_"),
+            [
+                link("Paddle", "https://paddle.org"),
+                text(", "),
+                link("docs", "docs"),
+                text(" and "),
+                link("site", "https://x.org"),
+                text("[1]_"),
+            ]
+        );
+        assert_eq!(
+            parse_inline("See [#note]_."),
+            [
+                text("See "),
+                Inline::FootnoteRef("#note".to_string()),
+                text(".")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_markup() {
+        assert_eq!(parse_inline("2 * 3 * 4"), [text("2 * 3 * 4")]);
+        assert_eq!(parse_inline("a*b*c"), [text("a*b*c")]);
+        assert_eq!(parse_inline("*open"), [text("*open")]);
+        assert_eq!(
+            parse_inline("list[int] and dict:"),
+            [text("list[int] and dict:")]
+        );
+        assert_eq!(parse_inline("``spaced ``"), [text("``spaced ``")]);
+    }
+}
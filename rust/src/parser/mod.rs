@@ -1,4 +1,9 @@
 pub mod argument;
+pub mod blocks;
 pub mod common;
+pub mod cst;
+pub mod directive;
 pub mod docstring;
+pub mod examples;
+pub mod inline;
 pub mod plain_paragraph;
@@ -1,8 +1,17 @@
 use crate::context::Context;
 use crate::cursor::Cursor;
-use crate::parser::common::{indented_paragraph, ParseResult};
+use crate::parser::common::{indented_block, lower_block, ParseResult};
+use crate::parser::cst::CstNode;
+use crate::span::{LineIndex, Span};
 
-pub fn parse_plain_paragraph(input: &mut Cursor, ctx: &mut Context) -> ParseResult<Vec<String>> {
-    let paragraph = indented_paragraph(input, ctx)?;
-    Ok(paragraph)
+pub fn plain_paragraph_node(input: &mut Cursor, ctx: &mut Context) -> ParseResult<CstNode> {
+    indented_block(input, ctx)
+}
+
+pub fn lower_plain_paragraph(
+    node: &CstNode,
+    index: &LineIndex,
+    tab_size: usize,
+) -> (Vec<String>, Vec<Span>) {
+    lower_block(node, index, tab_size)
 }
@@ -0,0 +1,426 @@
+//! Rendering in the Google style.
+
+use crate::indent::leading_spaces;
+use crate::parser::blocks::is_list_item;
+use crate::parser::docstring::DocstringTitle;
+use crate::schema::{Argument, Docstring, DocstringParagraph};
+
+/// Options of [`render_google`].
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level.
+    pub indent: usize,
+    /// Sections are emitted in this order, unlisted sections go last.
+    pub section_order: Vec<DocstringTitle>,
+    /// Number of blank lines between the description and each section.
+    pub blank_lines: usize,
+    /// Wrap prose at this width, `None` keeps the original line breaks.
+    pub width: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent: 4,
+            section_order: vec![
+                DocstringTitle::Deprecated,
+                DocstringTitle::Args,
+                DocstringTitle::Returns,
+                DocstringTitle::Raises,
+                DocstringTitle::Examples,
+                DocstringTitle::Notes,
+                DocstringTitle::Warnings,
+            ],
+            blank_lines: 1,
+            width: None,
+        }
+    }
+}
+
+/// A section together with the raw lines following it up to the next section.
+struct Block<'a> {
+    section: Option<&'a DocstringParagraph>,
+    trailing: Vec<&'a str>,
+}
+
+fn title_of(paragraph: &DocstringParagraph) -> Option<DocstringTitle> {
+    match paragraph {
+        DocstringParagraph::Args(_) => Some(DocstringTitle::Args),
+        DocstringParagraph::Returns(_) => Some(DocstringTitle::Returns),
+        DocstringParagraph::Raises(_) => Some(DocstringTitle::Raises),
+        DocstringParagraph::Examples(_) => Some(DocstringTitle::Examples),
+        DocstringParagraph::Note(_) => Some(DocstringTitle::Notes),
+        DocstringParagraph::Warning(_) => Some(DocstringTitle::Warnings),
+        DocstringParagraph::Deprecated(_) => Some(DocstringTitle::Deprecated),
+        DocstringParagraph::Raw(_) => None,
+    }
+}
+
+fn split_blocks(docstring: &Docstring) -> Vec<Block<'_>> {
+    let mut blocks = vec![Block {
+        section: None,
+        trailing: vec![],
+    }];
+    for section in docstring {
+        match &section.paragraph {
+            DocstringParagraph::Raw(line) => blocks.last_mut().unwrap().trailing.push(line),
+            section => blocks.push(Block {
+                section: Some(section),
+                trailing: vec![],
+            }),
+        }
+    }
+    blocks
+}
+
+/// Group lines into paragraphs separated by blank lines.
+fn paragraphs<'a>(lines: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut res: Vec<Vec<&str>> = vec![];
+    let mut current = vec![];
+    for line in lines {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                res.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line.trim_end());
+        }
+    }
+    if !current.is_empty() {
+        res.push(current);
+    }
+    res
+}
+
+/// Whether a line is plain text that may be rewrapped, that is not indented
+/// deeper than `margin` and not starting a list item, a directive or a
+/// doctest.
+fn is_plain(line: &str, margin: usize) -> bool {
+    let text = line.trim_start();
+    leading_spaces(line) == margin
+        && !is_list_item(text)
+        && !text.starts_with(".. ")
+        && !text.starts_with(">>>")
+}
+
+/// Render prose lines with the given prefixes, wrapping them if requested.
+///
+/// `first_prefix` is only used for the first line of the first paragraph.
+/// Only plain text is rewrapped, list items, directives and indented lines
+/// such as literal blocks are kept as they are.
+fn render_prose(
+    lines: &[&str],
+    first_prefix: &str,
+    prefix: &str,
+    options: &FormatOptions,
+) -> Vec<String> {
+    let margin = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| leading_spaces(line))
+        .min()
+        .unwrap_or(0);
+    let mut res = vec![];
+    for (i, paragraph) in paragraphs(lines).iter().enumerate() {
+        if i > 0 {
+            res.push("".to_string());
+        }
+        let initial = if i == 0 { first_prefix } else { prefix };
+        let start = res.len();
+        let mut plain: Vec<&str> = vec![];
+        let flush = |plain: &mut Vec<&str>, res: &mut Vec<String>| {
+            let (Some(width), false) = (options.width, plain.is_empty()) else {
+                return;
+            };
+            let text = plain
+                .drain(..)
+                .map(|line| line.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let wrap_options = textwrap::Options::new(width)
+                .initial_indent(if res.len() == start { initial } else { prefix })
+                .subsequent_indent(prefix)
+                .break_words(false);
+            res.extend(
+                textwrap::wrap(&text, wrap_options)
+                    .into_iter()
+                    .map(|line| line.into_owned()),
+            );
+        };
+        for line in paragraph {
+            if options.width.is_some() && is_plain(line, margin) {
+                plain.push(line);
+                continue;
+            }
+            flush(&mut plain, &mut res);
+            let line_prefix = if res.len() == start { initial } else { prefix };
+            res.push(format!("{}{}", line_prefix, line));
+        }
+        flush(&mut plain, &mut res);
+    }
+    if res.is_empty() && !first_prefix.is_empty() {
+        res.push(first_prefix.trim_end().to_string());
+    }
+    res
+}
+
+fn render_arg(arg: &Argument, options: &FormatOptions) -> Vec<String> {
+    let indent = " ".repeat(options.indent);
+    let head = match &arg.r#type {
+        Some(r#type) => format!("{}{} ({}): ", indent, arg.name, r#type.trim()),
+        None => format!("{}{}: ", indent, arg.name),
+    };
+    let desc: Vec<&str> = arg.desc.iter().map(|s| s.as_str()).collect();
+    render_prose(&desc, &head, &indent.repeat(2), options)
+}
+
+fn render_section(section: &DocstringParagraph, options: &FormatOptions) -> Vec<String> {
+    let indent = " ".repeat(options.indent);
+    let mut res = vec![];
+    if let Some(title) = title_of(section) {
+        res.push(format!("{}:", title.heads()[0]));
+    }
+    match section {
+        DocstringParagraph::Args(args) | DocstringParagraph::Raises(args) => {
+            for arg in args {
+                res.extend(render_arg(arg, options));
+            }
+        }
+        DocstringParagraph::Returns(lines) | DocstringParagraph::Deprecated(lines) => {
+            let lines: Vec<&str> = lines.iter().map(|s| s.as_str()).collect();
+            res.extend(render_prose(&lines, &indent, &indent, options));
+        }
+        DocstringParagraph::Note(text) | DocstringParagraph::Warning(text) => {
+            let lines: Vec<&str> = text.lines().collect();
+            res.extend(render_prose(&lines, &indent, &indent, options));
+        }
+        DocstringParagraph::Examples(lines) => {
+            let end = lines
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(0, |i| i + 1);
+            let start = lines
+                .iter()
+                .position(|line| !line.trim().is_empty())
+                .unwrap_or(end);
+            for line in &lines[start..end] {
+                if line.trim().is_empty() {
+                    res.push("".to_string());
+                } else {
+                    res.push(format!("{}{}", indent, line.trim_end()));
+                }
+            }
+        }
+        DocstringParagraph::Raw(line) => res.push(line.trim_end().to_string()),
+    }
+    res
+}
+
+/// Render a docstring in the Google style, normalizing its layout.
+pub fn render_google(docstring: &Docstring, options: &FormatOptions) -> String {
+    let mut blocks = split_blocks(docstring);
+    let order = |block: &Block| match block.section.and_then(title_of) {
+        Some(title) => options
+            .section_order
+            .iter()
+            .position(|t| *t == title)
+            .map_or(options.section_order.len() + 1, |i| i + 1),
+        None => 0,
+    };
+    blocks.sort_by_key(order);
+
+    let mut rendered: Vec<Vec<String>> = vec![];
+    for block in &blocks {
+        if let Some(section) = block.section {
+            rendered.push(render_section(section, options));
+        }
+        let trailing = render_prose(&block.trailing, "", "", options);
+        if !trailing.is_empty() {
+            rendered.push(trailing);
+        }
+    }
+    rendered
+        .into_iter()
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<_>>()
+        .join(&"\n".repeat(options.blank_lines + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::cursor::Cursor;
+    use crate::options::ParseOptions;
+    use crate::parser::docstring::parse_docstring;
+    use crate::utils::cleandoc;
+
+    fn format(doc: &str, options: &FormatOptions) -> String {
+        let doc = cleandoc(doc, None);
+        let mut cursor = Cursor::new(&doc);
+        let parse_options = ParseOptions::default();
+        let mut ctx = Context::new(0, &parse_options);
+        render_google(&parse_docstring(&mut cursor, &mut ctx).unwrap(), options)
+    }
+
+    #[test]
+    fn test_render_args() {
+        let doc = "
+        Summary line.
+        Args:
+          arg1  (int ): Description of arg1
+          arg2: Description of arg2
+                continued
+        ";
+        assert_eq!(
+            format(doc, &FormatOptions::default()),
+            "Summary line.\n\nArgs:\n    arg1 (int): Description of arg1\n    arg2: Description of arg2\n        continued"
+        );
+    }
+
+    #[test]
+    fn test_render_normalize_heading() {
+        let doc = "
+        Parameters:
+            arg1 (int): Description of arg1
+        ";
+        assert_eq!(
+            format(doc, &FormatOptions::default()),
+            "Args:\n    arg1 (int): Description of arg1"
+        );
+    }
+
+    #[test]
+    fn test_render_section_order() {
+        let doc = "
+        Summary line.
+
+        Returns:
+            Description of return value
+        Args:
+            arg1 (int): Description of arg1
+        ";
+        assert_eq!(
+            format(doc, &FormatOptions::default()),
+            "Summary line.\n\nArgs:\n    arg1 (int): Description of arg1\n\nReturns:\n    Description of return value"
+        );
+    }
+
+    #[test]
+    fn test_render_blank_lines() {
+        let doc = "
+        Summary line.
+
+
+
+        More description.
+        Returns:
+            Description of return value
+        ";
+        let options = FormatOptions {
+            blank_lines: 2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(doc, &options),
+            "Summary line.\n\nMore description.\n\n\nReturns:\n    Description of return value"
+        );
+    }
+
+    #[test]
+    fn test_render_wrap() {
+        let doc = "
+        Args:
+            arg1 (int): A rather long description of arg1
+                that continues here.
+        ";
+        let options = FormatOptions {
+            indent: 2,
+            width: Some(30),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(doc, &options),
+            "Args:\n  arg1 (int): A rather long\n    description of arg1 that\n    continues here."
+        );
+    }
+
+    #[test]
+    fn test_render_wrap_keeps_layout() {
+        let doc = "
+        Summary line that is long enough to be wrapped.
+
+        Args:
+            x (int): The x, which is described at some length.
+
+                - a
+                - b
+
+                .. code-block:: python
+
+                    x = 1
+
+                .. warning:: Careful.
+        ";
+        let options = FormatOptions {
+            width: Some(30),
+            ..FormatOptions::default()
+        };
+        let formatted = format(doc, &options);
+        let docstring = crate::parse(&formatted).unwrap();
+        let desc = &docstring.param("x").unwrap().desc;
+        assert_eq!(
+            desc[..],
+            [
+                "The x, which",
+                "is described at some",
+                "length.",
+                "",
+                "- a",
+                "- b",
+                "",
+                ".. code-block:: python",
+                "",
+                "    x = 1",
+                "",
+                ".. warning:: Careful.",
+            ]
+        );
+        assert_eq!(format(&formatted, &options), formatted);
+    }
+
+    #[test]
+    fn test_render_examples_verbatim() {
+        let doc = "
+        Examples:
+
+            >>> x = 1   +   1
+            >>> x
+            2
+        ";
+        let options = FormatOptions {
+            width: Some(10),
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(doc, &options),
+            "Examples:\n    >>> x = 1   +   1\n    >>> x\n    2"
+        );
+    }
+
+    #[test]
+    fn test_render_deprecated_first() {
+        let doc = "
+        Summary line.
+
+        Args:
+            x: The x.
+        Deprecated:
+            2.5 Use g instead.
+        ";
+        assert_eq!(
+            format(doc, &FormatOptions::default()),
+            "Summary line.\n\nDeprecated:\n    2.5 Use g instead.\n\nArgs:\n    x: The x."
+        );
+    }
+}
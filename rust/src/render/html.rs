@@ -0,0 +1,510 @@
+//! Rendering as HTML fragments.
+
+use std::collections::HashSet;
+
+use crate::parser::blocks::parse_blocks;
+use crate::parser::docstring::DocstringTitle;
+use crate::render::{admonition_title, chunks, is_web_url, role_target, role_title, Chunk};
+use crate::schema::{
+    Argument, Block, BlockKind, DirectiveKind, Docstring, DocstringParagraph, ExampleItem, Inline,
+    Section,
+};
+
+/// Resolves the role and target of a cross-reference, e.g. `("func",
+/// "paddle.add")`, to a URL.
+pub type Resolve<'a> = &'a dyn Fn(&str, &str) -> Option<String>;
+
+/// Options of [`render_html_with`].
+#[derive(Clone)]
+pub struct HtmlOptions<'a> {
+    /// Prepended to the anchor IDs, e.g. the qualified name of the object,
+    /// so that several docstrings can share a page.
+    pub id_prefix: String,
+    /// Level of the section headings, from 1 to 6.
+    pub heading_level: usize,
+    /// Links Sphinx roles such as ``:func:`paddle.add` ``. Unresolved roles
+    /// are rendered as code.
+    pub resolve: Option<Resolve<'a>>,
+}
+
+impl Default for HtmlOptions<'_> {
+    fn default() -> Self {
+        HtmlOptions {
+            id_prefix: String::new(),
+            heading_level: 2,
+            resolve: None,
+        }
+    }
+}
+
+/// Escape text for element contents and quoted attribute values.
+fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Hands out anchor IDs, suffixing the repeated ones with a counter.
+struct Ids<'p> {
+    prefix: &'p str,
+    used: HashSet<String>,
+}
+
+impl Ids<'_> {
+    fn next(&mut self, parts: &[&str]) -> String {
+        let slug: Vec<String> = std::iter::once(self.prefix)
+            .chain(parts.iter().copied())
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                part.chars()
+                    .map(|c| {
+                        if c.is_alphanumeric() || "-_.".contains(c) {
+                            c
+                        } else {
+                            '-'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let base = slug.join("-");
+        let mut id = base.clone();
+        let mut n = 1;
+        while !self.used.insert(id.clone()) {
+            n += 1;
+            id = format!("{base}-{n}");
+        }
+        id
+    }
+}
+
+struct Renderer<'o, 'a> {
+    options: &'o HtmlOptions<'a>,
+    ids: Ids<'o>,
+}
+
+impl Renderer<'_, '_> {
+    fn inlines(&self, nodes: &[Inline]) -> String {
+        let mut res = String::new();
+        for node in nodes {
+            match node {
+                Inline::Text(text) => res.push_str(&escape(text)),
+                Inline::Literal(text) => res.push_str(&format!("<code>{}</code>", escape(text))),
+                Inline::Emphasis(children) => {
+                    res.push_str(&format!("<em>{}</em>", self.inlines(children)))
+                }
+                Inline::Strong(children) => {
+                    res.push_str(&format!("<strong>{}</strong>", self.inlines(children)))
+                }
+                Inline::Role { name, text } if name == "math" => res.push_str(&format!(
+                    "<span class=\"math\">\\({}\\)</span>",
+                    escape(text)
+                )),
+                Inline::Role { name, text } => res.push_str(&self.role(name, text)),
+                Inline::Link { text, target } if is_web_url(target) => res.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(target),
+                    escape(text)
+                )),
+                Inline::Link { text, .. } => res.push_str(&escape(text)),
+                Inline::FootnoteRef(label) => res.push_str(&format!(
+                    "<sup class=\"footnote-ref\">[{}]</sup>",
+                    escape(label)
+                )),
+            }
+        }
+        res
+    }
+
+    fn role(&self, name: &str, text: &str) -> String {
+        let code = format!(
+            "<code class=\"xref {}\">{}</code>",
+            escape(&name.replace(':', "-")),
+            escape(&role_title(text))
+        );
+        let url = self
+            .options
+            .resolve
+            .zip(role_target(text))
+            .and_then(|(resolve, target)| resolve(name, &target));
+        match url {
+            Some(url) => format!(
+                "<a class=\"reference\" href=\"{}\">{}</a>",
+                escape(&url),
+                code
+            ),
+            None => code,
+        }
+    }
+
+    fn blocks(&self, blocks: &[Block]) -> Vec<String> {
+        let mut res = vec![];
+        for block in blocks {
+            match &block.kind {
+                BlockKind::Paragraph(nodes) => res.push(format!("<p>{}</p>", self.inlines(nodes))),
+                BlockKind::BulletList(items) => {
+                    res.push("<ul>".to_string());
+                    res.extend(self.items(items));
+                    res.push("</ul>".to_string());
+                }
+                BlockKind::OrderedList { start, items } => {
+                    match start {
+                        1 => res.push("<ol>".to_string()),
+                        start => res.push(format!("<ol start=\"{start}\">")),
+                    }
+                    res.extend(self.items(items));
+                    res.push("</ol>".to_string());
+                }
+                BlockKind::DefinitionList(items) => {
+                    res.push("<dl>".to_string());
+                    for item in items {
+                        res.push(format!("<dt>{}</dt>", self.inlines(&item.term)));
+                        res.push(format!(
+                            "<dd>{}</dd>",
+                            self.blocks(&item.definition).join("\n")
+                        ));
+                    }
+                    res.push("</dl>".to_string());
+                }
+                BlockKind::LiteralBlock(text) => res.push(pre(text, None)),
+                BlockKind::BlockQuote(children) => res.push(format!(
+                    "<blockquote>\n{}\n</blockquote>",
+                    self.blocks(children).join("\n")
+                )),
+            }
+        }
+        res
+    }
+
+    /// The lines of a text, admonitions and code blocks included.
+    fn text(&mut self, lines: &[String]) -> Vec<String> {
+        let mut res = vec![];
+        for chunk in chunks(lines) {
+            match chunk {
+                Chunk::Text(lines) => res.extend(self.blocks(&parse_blocks(&lines, &[]))),
+                Chunk::Directive(directive) => match &directive.kind {
+                    DirectiveKind::CodeBlock { language } => {
+                        res.push(pre(&directive.content.join("\n"), language.as_deref()))
+                    }
+                    kind => {
+                        let title = admonition_title(kind).unwrap_or_default();
+                        let body = self.text(&directive.content);
+                        res.extend(self.admonition(&directive.name, &title, body));
+                    }
+                },
+            }
+        }
+        res
+    }
+
+    fn items(&self, items: &[Vec<Block>]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| match item.as_slice() {
+                // A lone paragraph goes without its `<p>`, as in tight lists.
+                [Block {
+                    kind: BlockKind::Paragraph(nodes),
+                    ..
+                }] => format!("<li>{}</li>", self.inlines(nodes)),
+                blocks => format!("<li>{}</li>", self.blocks(blocks).join("\n")),
+            })
+            .collect()
+    }
+
+    fn args(&mut self, section: &str, args: &[Argument]) -> Vec<String> {
+        let mut res = vec![format!("<dl class=\"{section}\">")];
+        for arg in args {
+            let id = self.ids.next(&[section, &arg.name]);
+            let mut term = format!("<code class=\"name\">{}</code>", escape(&arg.name));
+            if let Some(r#type) = &arg.r#type {
+                term.push_str(&format!(
+                    " (<code class=\"type\">{}</code>)",
+                    escape(r#type.trim())
+                ));
+            }
+            res.push(format!("<dt id=\"{}\">{}</dt>", escape(&id), term));
+            let desc = self.text(&arg.desc);
+            res.push(format!("<dd>{}</dd>", desc.join("\n")));
+        }
+        res.push("</dl>".to_string());
+        res
+    }
+
+    fn examples(&mut self, section: &Section) -> Vec<String> {
+        let mut res = vec![];
+        for item in section.examples().unwrap_or_default() {
+            match item {
+                ExampleItem::Text { lines, .. } => res.extend(self.text(&lines)),
+                ExampleItem::Doctest(doctest) => {
+                    let mut code = vec![];
+                    for (i, line) in doctest.source.iter().enumerate() {
+                        let prompt = if i == 0 { ">>> " } else { "... " };
+                        code.push(format!("{prompt}{line}").trim_end().to_string());
+                    }
+                    code.extend(doctest.want.iter().cloned());
+                    res.push(pre(&code.join("\n"), Some("python")));
+                }
+                ExampleItem::CodeBlock(block) => {
+                    res.push(pre(&block.code, block.language.as_deref()))
+                }
+            }
+        }
+        res
+    }
+
+    fn section(&mut self, section: &Section) -> Vec<String> {
+        let lines = section.paragraph.lines();
+        let (title, body) = match &section.paragraph {
+            DocstringParagraph::Args(args) => (DocstringTitle::Args, self.args("params", args)),
+            DocstringParagraph::Raises(args) => (DocstringTitle::Raises, self.args("raises", args)),
+            DocstringParagraph::Returns(_) => (DocstringTitle::Returns, self.text(&lines)),
+            DocstringParagraph::Examples(_) => (DocstringTitle::Examples, self.examples(section)),
+            DocstringParagraph::Note(_) => {
+                let body = self.text(&lines);
+                return self.admonition("note", "Note", body);
+            }
+            DocstringParagraph::Warning(_) => {
+                let body = self.text(&lines);
+                return self.admonition("warning", "Warning", body);
+            }
+            DocstringParagraph::Deprecated(_) => {
+                let body = self.text(&lines);
+                return self.admonition("deprecated", "Deprecated", body);
+            }
+            DocstringParagraph::Raw(_) => return self.text(&lines),
+        };
+        let name = title.heads()[0];
+        let id = self.ids.next(&[&name.to_lowercase()]);
+        let level = self.options.heading_level.clamp(1, 6);
+        let mut res = vec![
+            format!("<section id=\"{}\">", escape(&id)),
+            format!("<h{level}>{name}</h{level}>"),
+        ];
+        res.extend(body);
+        res.push("</section>".to_string());
+        res
+    }
+
+    fn admonition(&mut self, class: &str, title: &str, body: Vec<String>) -> Vec<String> {
+        let id = self.ids.next(&[class]);
+        let mut res = vec![
+            format!(
+                "<aside id=\"{}\" class=\"admonition {class}\">",
+                escape(&id)
+            ),
+            format!("<p class=\"admonition-title\">{title}</p>"),
+        ];
+        res.extend(body);
+        res.push("</aside>".to_string());
+        res
+    }
+}
+
+/// `<pre><code>` with a `language-*` class when the language is known.
+fn pre(code: &str, language: Option<&str>) -> String {
+    match language {
+        Some(language) => format!(
+            "<pre><code class=\"language-{}\">{}</code></pre>",
+            escape(language),
+            escape(code)
+        ),
+        None => format!("<pre><code>{}</code></pre>", escape(code)),
+    }
+}
+
+/// Render a docstring as an HTML fragment with the default options.
+pub fn render_html(docstring: &Docstring) -> String {
+    render_html_with(docstring, &HtmlOptions::default())
+}
+
+/// Render a docstring as an HTML fragment: the description as paragraphs and
+/// lists, a `<section>` per section with `<dl>` for parameters and raised
+/// exceptions, `<aside>` for notes, warnings and admonition directives and
+/// `<pre><code>` for examples and code blocks.
+///
+/// Sections and entries get IDs such as `args`, `params-x` or `raises-ValueError`,
+/// prefixed with [`HtmlOptions::id_prefix`].
+pub fn render_html_with(docstring: &Docstring, options: &HtmlOptions) -> String {
+    let mut renderer = Renderer {
+        options,
+        ids: Ids {
+            prefix: &options.id_prefix,
+            used: HashSet::new(),
+        },
+    };
+    let mut res: Vec<String> = vec![];
+    let mut raw: Vec<String> = vec![];
+    for section in docstring {
+        match &section.paragraph {
+            DocstringParagraph::Raw(line) => raw.push(line.clone()),
+            _ => {
+                res.extend(renderer.text(&raw));
+                raw.clear();
+                res.extend(renderer.section(section));
+            }
+        }
+    }
+    res.extend(renderer.text(&raw));
+    let mut res = res.join("\n");
+    res.push('\n');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "
+Add <two> tensors, see :func:`paddle.add` and :class:`!Tensor`.
+
+Args:
+    x (Tensor): The first one.
+    x (int): The same name.
+Raises:
+    ValueError: If `a & b`.
+Warnings:
+    Slow.
+Examples:
+    >>> add(1, 2) < 4
+    True
+";
+
+    fn parse(doc: &str) -> Docstring {
+        let options = crate::ParseOptions {
+            sections: vec![
+                DocstringTitle::Args,
+                DocstringTitle::Raises,
+                DocstringTitle::Warnings,
+                DocstringTitle::Examples,
+            ],
+            ..crate::ParseOptions::default()
+        };
+        crate::parse_with(doc, &options).unwrap()
+    }
+
+    #[test]
+    fn test_render_html() {
+        assert_eq!(
+            render_html(&parse(DOC)),
+            r#"<p>Add &lt;two&gt; tensors, see <code class="xref func">paddle.add</code> and <code class="xref class">Tensor</code>.</p>
+<section id="args">
+<h2>Args</h2>
+<dl class="params">
+<dt id="params-x"><code class="name">x</code> (<code class="type">Tensor</code>)</dt>
+<dd><p>The first one.</p></dd>
+<dt id="params-x-2"><code class="name">x</code> (<code class="type">int</code>)</dt>
+<dd><p>The same name.</p></dd>
+</dl>
+</section>
+<section id="raises">
+<h2>Raises</h2>
+<dl class="raises">
+<dt id="raises-ValueError"><code class="name">ValueError</code></dt>
+<dd><p>If <code>a &amp; b</code>.</p></dd>
+</dl>
+</section>
+<aside id="warning" class="admonition warning">
+<p class="admonition-title">Warning</p>
+<p>Slow.</p>
+</aside>
+<section id="examples">
+<h2>Examples</h2>
+<pre><code class="language-python">&gt;&gt;&gt; add(1, 2) &lt; 4
+True</code></pre>
+</section>
+"#
+        );
+    }
+
+    #[test]
+    fn test_resolve_and_prefix() {
+        let resolve = |role: &str, target: &str| {
+            (role == "func").then(|| format!("https://docs/{target}.html?a=1&b=2"))
+        };
+        let options = HtmlOptions {
+            id_prefix: "paddle.add".to_string(),
+            heading_level: 3,
+            resolve: Some(&resolve),
+        };
+        let html = render_html_with(&parse(DOC), &options);
+        assert!(html.contains(
+            r#"<a class="reference" href="https://docs/paddle.add.html?a=1&amp;b=2"><code class="xref func">paddle.add</code></a>"#
+        ));
+        assert!(html.contains(r#"<code class="xref class">Tensor</code>"#));
+        assert!(html.contains(r#"<section id="paddle.add-args">"#));
+        assert!(html.contains("<h3>Args</h3>"));
+        assert!(html.contains(r#"<dt id="paddle.add-params-x">"#));
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let doc = "
+Steps:
+
+3. one
+4. `site <javascript:alert(1)>`_
+
+Example::
+
+    <b>
+";
+        assert_eq!(
+            render_html(&parse(doc)),
+            "<p>Steps:</p>
+<ol start=\"3\">
+<li>one</li>
+<li>site</li>
+</ol>
+<p>Example:</p>
+<pre><code>&lt;b&gt;</code></pre>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_directives() {
+        let doc = "
+Summary.
+
+.. note:: Keep it short.
+
+Args:
+    x: The x.
+
+        .. code-block:: python
+
+            x = 1
+
+        .. versionadded:: 2.6
+";
+        assert_eq!(
+            render_html(&parse(doc)),
+            r#"<p>Summary.</p>
+<aside id="note" class="admonition note">
+<p class="admonition-title">Note</p>
+<p>Keep it short.</p>
+</aside>
+<section id="args">
+<h2>Args</h2>
+<dl class="params">
+<dt id="params-x"><code class="name">x</code></dt>
+<dd><p>The x.</p>
+<pre><code class="language-python">x = 1</code></pre>
+<aside id="versionadded" class="admonition versionadded">
+<p class="admonition-title">Added in version 2.6</p>
+</aside></dd>
+</dl>
+</section>
+"#
+        );
+    }
+}
@@ -0,0 +1,558 @@
+//! Rendering as CommonMark.
+
+use crate::parser::blocks::parse_blocks;
+use crate::parser::docstring::DocstringTitle;
+use crate::render::{admonition_title, chunks, is_web_url, role_title, Chunk};
+use crate::schema::{
+    Argument, Block, BlockKind, Directive, DirectiveKind, Docstring, DocstringParagraph,
+    ExampleItem, Inline, Section,
+};
+
+/// How [`render_markdown_with`] lays parameters and raised exceptions out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ParamLayout {
+    /// A table with a row per entry.
+    #[default]
+    Table,
+    /// A bullet list, the name in bold.
+    List,
+}
+
+/// Options of [`render_markdown_with`].
+#[derive(Clone, Debug)]
+pub struct MarkdownOptions {
+    /// Level of the section headings, from 1 to 6.
+    pub heading_level: usize,
+    /// How `Args` and `Raises` entries are laid out.
+    pub layout: ParamLayout,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> MarkdownOptions {
+        MarkdownOptions {
+            heading_level: 2,
+            layout: ParamLayout::default(),
+        }
+    }
+}
+
+/// Escape the characters that would start markup, `line_start` telling
+/// whether `text` starts a line. Pipes are left to [`cell`].
+fn escape(text: &str, line_start: bool) -> String {
+    let mut res = String::with_capacity(text.len());
+    let chars: Vec<char> = text.chars().collect();
+    let mut at_start = line_start;
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1).copied();
+        let intraword =
+            prev.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric);
+        let block_marker = at_start
+            && (matches!(c, '#' | '>') || matches!(c, '-' | '+') && next.is_none_or(|c| c == ' '));
+        if block_marker || "\\`*[]<".contains(c) || c == '_' && !intraword {
+            res.push('\\');
+        }
+        res.push(c);
+        if c == '\n' {
+            at_start = true;
+        } else if c != ' ' {
+            at_start = false;
+        }
+    }
+    res
+}
+
+/// A code span holding `text`, with enough backquotes.
+fn code_span(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let ticks = "`".repeat(longest + 1);
+    let text = text.replace('\n', " ");
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{ticks} {text} {ticks}")
+    } else {
+        format!("{ticks}{text}{ticks}")
+    }
+}
+
+/// A fenced code block, the fence longer than any backquote run of `code`.
+fn fenced(code: &str, language: &str) -> Vec<String> {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let mut res = vec![format!("{fence}{language}")];
+    res.extend(code.lines().map(str::to_string));
+    res.push(fence);
+    res
+}
+
+fn render_inlines(nodes: &[Inline], line_start: bool) -> String {
+    let mut res = String::new();
+    for node in nodes {
+        let start = if res.is_empty() {
+            line_start
+        } else {
+            res.ends_with('\n')
+        };
+        match node {
+            Inline::Text(text) => res.push_str(&escape(text, start)),
+            Inline::Literal(text) => res.push_str(&code_span(text)),
+            Inline::Emphasis(children) => {
+                res.push_str(&format!("*{}*", render_inlines(children, false)))
+            }
+            Inline::Strong(children) => {
+                res.push_str(&format!("**{}**", render_inlines(children, false)))
+            }
+            Inline::Role { text, .. } => res.push_str(&code_span(&role_title(text))),
+            Inline::Link { text, target } if is_web_url(target) => {
+                res.push_str(&format!("[{}]({})", escape(text, false), target))
+            }
+            Inline::Link { text, .. } => res.push_str(&escape(text, start)),
+            Inline::FootnoteRef(label) => res.push_str(&format!("\\[{}\\]", escape(label, false))),
+        }
+    }
+    res
+}
+
+/// Prefix the first line with `first` and indent the others as much.
+fn prefixed(lines: Vec<String>, first: &str) -> Vec<String> {
+    let indent = " ".repeat(first.chars().count());
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| match (i, line.is_empty()) {
+            (0, _) => format!("{first}{line}"),
+            (_, true) => line,
+            _ => format!("{indent}{line}"),
+        })
+        .collect()
+}
+
+fn render_blocks(blocks: &[Block]) -> Vec<String> {
+    let mut res: Vec<String> = vec![];
+    for block in blocks {
+        if !res.is_empty() {
+            res.push(String::new());
+        }
+        match &block.kind {
+            BlockKind::Paragraph(nodes) => {
+                res.extend(render_inlines(nodes, true).lines().map(str::to_string))
+            }
+            BlockKind::BulletList(items) => {
+                for item in items {
+                    res.extend(prefixed(render_blocks(item), "- "));
+                }
+            }
+            BlockKind::OrderedList { start, items } => {
+                for (n, item) in items.iter().enumerate() {
+                    res.extend(prefixed(render_blocks(item), &format!("{}. ", start + n)));
+                }
+            }
+            BlockKind::DefinitionList(items) => {
+                for item in items {
+                    let term = format!("**{}**", render_inlines(&item.term, false));
+                    let mut lines = render_blocks(&item.definition);
+                    match lines.first_mut() {
+                        Some(first) => *first = format!("{term}: {first}"),
+                        None => lines.push(term),
+                    }
+                    res.extend(prefixed(lines, "- "));
+                }
+            }
+            BlockKind::LiteralBlock(text) => res.extend(fenced(text, "")),
+            BlockKind::BlockQuote(children) => res.extend(quoted(render_blocks(children))),
+        }
+    }
+    res
+}
+
+fn render_directive(directive: &Directive) -> Vec<String> {
+    match (&directive.kind, admonition_title(&directive.kind)) {
+        (DirectiveKind::CodeBlock { language }, _) => fenced(
+            &directive.content.join("\n"),
+            language.as_deref().unwrap_or(""),
+        ),
+        (_, Some(title)) => admonition(&title, render_text(&directive.content)),
+        (_, None) => render_text(&directive.content),
+    }
+}
+
+/// The lines of a text, admonitions and code blocks included.
+fn render_text(lines: &[String]) -> Vec<String> {
+    let mut res: Vec<String> = vec![];
+    for chunk in chunks(lines) {
+        let lines = match chunk {
+            Chunk::Text(lines) => render_blocks(&parse_blocks(&lines, &[])),
+            Chunk::Directive(directive) => render_directive(&directive),
+        };
+        if lines.is_empty() {
+            continue;
+        }
+        if !res.is_empty() {
+            res.push(String::new());
+        }
+        res.extend(lines);
+    }
+    res
+}
+
+fn quoted(lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect()
+}
+
+/// A table cell: the text on one line, line breaks turned into `<br>`.
+fn cell(lines: &[String]) -> String {
+    render_text(lines)
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("<br>")
+        .replace('|', "\\|")
+}
+
+fn render_args(
+    args: &[Argument],
+    name: &str,
+    with_type: bool,
+    options: &MarkdownOptions,
+) -> Vec<String> {
+    let mut res = vec![];
+    match options.layout {
+        ParamLayout::Table => {
+            if with_type {
+                res.push(format!("| {name} | Type | Description |"));
+                res.push("| --- | --- | --- |".to_string());
+            } else {
+                res.push(format!("| {name} | Description |"));
+                res.push("| --- | --- |".to_string());
+            }
+            for arg in args {
+                let desc = cell(&arg.desc);
+                if with_type {
+                    let r#type = arg
+                        .r#type
+                        .as_deref()
+                        .map_or(String::new(), |t| code_span(t.trim()));
+                    res.push(format!(
+                        "| {} | {} | {} |",
+                        code_span(&arg.name),
+                        r#type.replace('|', "\\|"),
+                        desc
+                    ));
+                } else {
+                    res.push(format!("| {} | {} |", code_span(&arg.name), desc));
+                }
+            }
+        }
+        ParamLayout::List => {
+            for arg in args {
+                let mut head = format!("**{}**", escape(&arg.name, false));
+                if let Some(r#type) = &arg.r#type {
+                    head.push_str(&format!(" ({})", code_span(r#type.trim())));
+                }
+                let mut lines = render_text(&arg.desc);
+                match lines.first_mut() {
+                    Some(first) => *first = format!("{head}: {first}"),
+                    None => lines.push(head),
+                }
+                res.extend(prefixed(lines, "- "));
+            }
+        }
+    }
+    res
+}
+
+fn render_examples(section: &Section) -> Vec<String> {
+    let mut res: Vec<String> = vec![];
+    for item in section.examples().unwrap_or_default() {
+        let lines = match item {
+            ExampleItem::Text { lines, .. } => render_text(&lines),
+            ExampleItem::Doctest(doctest) => {
+                let mut code = vec![];
+                for (i, line) in doctest.source.iter().enumerate() {
+                    let prompt = if i == 0 { ">>> " } else { "... " };
+                    code.push(format!("{prompt}{line}").trim_end().to_string());
+                }
+                code.extend(doctest.want.iter().cloned());
+                fenced(&code.join("\n"), "python")
+            }
+            ExampleItem::CodeBlock(block) => {
+                fenced(&block.code, block.language.as_deref().unwrap_or(""))
+            }
+        };
+        if lines.is_empty() {
+            continue;
+        }
+        if !res.is_empty() {
+            res.push(String::new());
+        }
+        res.extend(lines);
+    }
+    res
+}
+
+fn heading(title: DocstringTitle, options: &MarkdownOptions) -> String {
+    format!(
+        "{} {}",
+        "#".repeat(options.heading_level.clamp(1, 6)),
+        title.heads()[0]
+    )
+}
+
+/// An admonition as a block quote opening with its title in bold.
+fn admonition(title: &str, body: Vec<String>) -> Vec<String> {
+    let mut lines = vec![format!("**{title}**")];
+    if !body.is_empty() {
+        lines.push(String::new());
+        lines.extend(body);
+    }
+    quoted(lines)
+}
+
+fn render_section(section: &Section, options: &MarkdownOptions) -> Vec<String> {
+    let text = || render_text(&section.paragraph.lines());
+    let (title, body) = match &section.paragraph {
+        DocstringParagraph::Args(args) => (
+            DocstringTitle::Args,
+            render_args(args, "Name", true, options),
+        ),
+        DocstringParagraph::Raises(args) => (
+            DocstringTitle::Raises,
+            render_args(args, "Exception", false, options),
+        ),
+        DocstringParagraph::Returns(_) => (DocstringTitle::Returns, text()),
+        DocstringParagraph::Examples(_) => (DocstringTitle::Examples, render_examples(section)),
+        DocstringParagraph::Note(_) => return admonition("Note", text()),
+        DocstringParagraph::Warning(_) => return admonition("Warning", text()),
+        DocstringParagraph::Deprecated(_) => return admonition("Deprecated", text()),
+        DocstringParagraph::Raw(_) => return text(),
+    };
+    let mut res = vec![heading(title, options)];
+    if !body.is_empty() {
+        res.push(String::new());
+        res.extend(body);
+    }
+    res
+}
+
+/// Render a docstring as CommonMark with the default options.
+pub fn render_markdown(docstring: &Docstring) -> String {
+    render_markdown_with(docstring, &MarkdownOptions::default())
+}
+
+/// Render a docstring as CommonMark: the description as paragraphs and
+/// lists, a heading per section, notes, warnings and admonition directives
+/// as block quotes, and examples and code blocks as fenced code blocks.
+pub fn render_markdown_with(docstring: &Docstring, options: &MarkdownOptions) -> String {
+    let mut parts: Vec<Vec<String>> = vec![];
+    let mut raw: Vec<String> = vec![];
+    let flush = |raw: &mut Vec<String>, parts: &mut Vec<Vec<String>>| {
+        let lines = render_text(raw);
+        if !lines.is_empty() {
+            parts.push(lines);
+        }
+        raw.clear();
+    };
+    for section in docstring {
+        match &section.paragraph {
+            DocstringParagraph::Raw(line) => raw.push(line.clone()),
+            _ => {
+                flush(&mut raw, &mut parts);
+                parts.push(render_section(section, options));
+            }
+        }
+    }
+    flush(&mut raw, &mut parts);
+    let mut res = parts
+        .into_iter()
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    res.push('\n');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(doc: &str, options: &MarkdownOptions) -> String {
+        render_markdown_with(&crate::parse(doc).unwrap(), options)
+    }
+
+    const DOC: &str = "
+Add two tensors, see :func:`paddle.add`.
+
+Args:
+    x (Tensor): The first one, a
+        - list, or
+        - tensor.
+    y (int|float): The second_one.
+Returns:
+    The *sum*.
+Raises:
+    ValueError: If the shapes differ.
+Examples:
+    Add:
+
+    >>> add(1, 2)
+    3
+";
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            render(DOC, &MarkdownOptions::default()),
+            "Add two tensors, see `paddle.add`.
+
+## Args
+
+| Name | Type | Description |
+| --- | --- | --- |
+| `x` | `Tensor` | The first one, a<br>- list, or<br>- tensor. |
+| `y` | `int\\|float` | The second_one. |
+
+## Returns
+
+The *sum*.
+
+## Raises
+
+| Exception | Description |
+| --- | --- |
+| `ValueError` | If the shapes differ. |
+
+## Examples
+
+Add:
+
+```python
+>>> add(1, 2)
+3
+```
+"
+        );
+    }
+
+    #[test]
+    fn test_render_list() {
+        let options = MarkdownOptions {
+            heading_level: 3,
+            layout: ParamLayout::List,
+        };
+        let rendered = render(DOC, &options);
+        assert!(rendered.contains(
+            "### Args
+
+- **x** (`Tensor`): The first one, a
+
+  - list, or
+  - tensor.
+- **y** (`int|float`): The second_one.
+"
+        ));
+        assert!(rendered.contains("- **ValueError**: If the shapes differ.\n"));
+    }
+
+    #[test]
+    fn test_render_admonitions_and_blocks() {
+        let doc = "
+Summary.
+
+Example::
+
+    x = `1`
+
+Deprecated:
+    2.5 Use g.
+";
+        assert_eq!(
+            render(doc, &MarkdownOptions::default()),
+            "Summary.
+
+Example:
+
+```
+x = `1`
+```
+
+> **Deprecated**
+>
+> 2.5 Use g.
+"
+        );
+    }
+
+    #[test]
+    fn test_render_directives() {
+        let doc = "
+Summary.
+
+.. note:: Keep it
+   short.
+
+.. code-block:: python
+
+    x = 1
+
+Returns:
+    The x.
+
+    .. warning:: Careful.
+";
+        assert_eq!(
+            render(doc, &MarkdownOptions::default()),
+            "Summary.
+
+> **Note**
+>
+> Keep it
+> short.
+
+```python
+x = 1
+```
+
+## Returns
+
+The x.
+
+> **Warning**
+>
+> Careful.
+"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("# a_b *c* _d [e]", true),
+            "\\# a_b \\*c\\* \\_d \\[e\\]"
+        );
+        assert_eq!(escape("- x\n> y", true), "\\- x\n\\> y");
+        assert_eq!(escape("a | b", false), "a | b");
+        assert_eq!(cell(&["a | b".to_string()]), "a \\| b");
+        let link = |target: &str| Inline::Link {
+            text: "here".to_string(),
+            target: target.to_string(),
+        };
+        assert_eq!(
+            render_inlines(&[link("https://example.com")], false),
+            "[here](https://example.com)"
+        );
+        assert_eq!(
+            render_inlines(&[link("javascript://alert(1)")], false),
+            "here"
+        );
+        assert_eq!(code_span("a`b"), "``a`b``");
+    }
+}
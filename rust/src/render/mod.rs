@@ -0,0 +1,129 @@
+//! Turn a [`crate::Docstring`] back into text.
+
+use crate::indent::leading_spaces;
+use crate::parser::directive::{directive, directive_at};
+use crate::schema::{Directive, DirectiveKind};
+use crate::span::Span;
+
+pub mod google;
+pub mod html;
+pub mod markdown;
+
+/// The text shown for the target of a Sphinx role: the title of
+/// `` `title <target>` ``, the last component of `` `~a.b.c` `` and the target
+/// itself otherwise.
+pub(crate) fn role_title(text: &str) -> String {
+    if let Some((title, _)) = text
+        .strip_suffix('>')
+        .and_then(|head| head.rsplit_once('<'))
+    {
+        if !title.trim().is_empty() {
+            return title.trim().to_string();
+        }
+    }
+    let target = text.trim_start_matches('!');
+    match target.strip_prefix('~') {
+        Some(path) => path.rsplit('.').next().unwrap_or(path).to_string(),
+        None => target.to_string(),
+    }
+}
+
+/// The target of a Sphinx role, without its title and `~`, `None` when a
+/// leading `!` asks for no link.
+pub(crate) fn role_target(text: &str) -> Option<String> {
+    if text.starts_with('!') {
+        return None;
+    }
+    let target = text
+        .strip_suffix('>')
+        .and_then(|head| head.rsplit_once('<'))
+        .map_or(text, |(_, target)| target);
+    Some(target.trim().trim_start_matches('~').to_string())
+}
+
+/// Whether a link target may become a link, which rules out `javascript:`
+/// and the like.
+pub(crate) fn is_web_url(target: &str) -> bool {
+    ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|scheme| target.starts_with(scheme))
+}
+
+/// A run of the lines of a text: lines to parse into blocks, or a directive
+/// with markup of its own.
+pub(crate) enum Chunk {
+    Text(Vec<String>),
+    Directive(Directive),
+}
+
+/// Split `lines` at the admonitions and code blocks starting at their
+/// margin, other directives stay text.
+pub(crate) fn chunks(lines: &[String]) -> Vec<Chunk> {
+    let margin = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| leading_spaces(line))
+        .min()
+        .unwrap_or(0);
+    let mut res = vec![];
+    let mut text = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let raw = directive_at(&lines[i..]).filter(|_| leading_spaces(&lines[i]) == margin);
+        let Some(raw) = raw else {
+            text.push(lines[i].clone());
+            i += 1;
+            continue;
+        };
+        let len = raw.len;
+        let directive = directive(raw, Span::default());
+        if admonition_title(&directive.kind).is_none()
+            && !matches!(directive.kind, DirectiveKind::CodeBlock { .. })
+        {
+            text.extend_from_slice(&lines[i..i + len]);
+        } else {
+            if !text.is_empty() {
+                res.push(Chunk::Text(std::mem::take(&mut text)));
+            }
+            res.push(Chunk::Directive(directive));
+        }
+        i += len;
+    }
+    if !text.is_empty() {
+        res.push(Chunk::Text(text));
+    }
+    res
+}
+
+/// The title of an admonition, `None` for the directives that are not one.
+pub(crate) fn admonition_title(kind: &DirectiveKind) -> Option<String> {
+    match kind {
+        DirectiveKind::Note => Some("Note".to_string()),
+        DirectiveKind::Warning => Some("Warning".to_string()),
+        DirectiveKind::Deprecated { version } => {
+            Some(format!("Deprecated since version {version}"))
+        }
+        DirectiveKind::VersionAdded { version } => Some(format!("Added in version {version}")),
+        DirectiveKind::VersionChanged { version } => Some(format!("Changed in version {version}")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_title() {
+        assert_eq!(role_title("paddle.add"), "paddle.add");
+        assert_eq!(role_title("~paddle.Tensor.add"), "add");
+        assert_eq!(role_title("the sum <paddle.add>"), "the sum");
+        assert_eq!(role_title("!paddle.add"), "paddle.add");
+        assert_eq!(role_target("~paddle.add").as_deref(), Some("paddle.add"));
+        assert_eq!(
+            role_target("sum <paddle.add>").as_deref(),
+            Some("paddle.add")
+        );
+        assert_eq!(role_target("!paddle.add"), None);
+    }
+}
@@ -1,18 +1,1118 @@
-#[derive(PartialEq, Debug, Clone)]
+//! The parsed form of a docstring.
+
+use crate::parser::blocks::parse_blocks;
+use crate::parser::directive::parse_directives;
+use crate::parser::examples::parse_examples;
+use crate::parser::inline::parse_inline;
+use crate::span::Span;
+use std::collections::HashMap;
+
+/// An entry of an `Args` or `Raises` section, `name (type): desc`.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Argument {
+    /// The argument name.
     pub name: String,
+    /// The text between the parentheses, `None` without parentheses.
     pub r#type: Option<String>,
+    /// The description, one item per line with the indentation removed.
     pub desc: Vec<String>,
+    /// The whole entry.
+    pub span: Span,
+    /// The name.
+    pub name_span: Span,
+    /// The text between the parentheses.
+    pub type_span: Option<Span>,
+    /// One span per line of `desc`.
+    pub desc_spans: Vec<Span>,
 }
 
+impl Argument {
+    /// The paragraphs, lists and other blocks of the description, parsed
+    /// from `desc` on each call.
+    pub fn blocks(&self) -> Vec<Block> {
+        parse_blocks(&self.desc, &self.desc_spans)
+    }
+
+    /// The inline markup of the description, parsed from `desc` on each call.
+    pub fn inlines(&self) -> Vec<Inline> {
+        parse_inline(&self.desc.join("\n"))
+    }
+
+    /// The directives of the description.
+    pub fn directives(&self) -> Vec<Directive> {
+        parse_directives(&self.desc, &self.desc_spans)
+    }
+}
+
+/// The contents of a section.
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
 pub enum DocstringParagraph {
+    /// A line outside of any section.
     Raw(String),
+    /// The text of a `Warnings` section.
     Warning(String),
+    /// The text of a `Notes` section.
     Note(String),
+    /// The entries of an `Args` section.
     Args(Vec<Argument>),
+    /// The lines of a `Returns` section.
     Returns(Vec<String>),
+    /// Entries of a `Raises` section, `name` holds the exception type.
+    Raises(Vec<Argument>),
+    /// The lines of an `Examples` section.
     Examples(Vec<String>),
+    /// The lines of a `Deprecated` section, the version first if given.
+    Deprecated(Vec<String>),
+}
+
+/// The variant of a [`DocstringParagraph`], without its contents.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SectionKind {
+    /// [`DocstringParagraph::Raw`].
+    Raw,
+    /// [`DocstringParagraph::Warning`].
+    Warning,
+    /// [`DocstringParagraph::Note`].
+    Note,
+    /// [`DocstringParagraph::Args`].
+    Args,
+    /// [`DocstringParagraph::Returns`].
+    Returns,
+    /// [`DocstringParagraph::Raises`].
+    Raises,
+    /// [`DocstringParagraph::Examples`].
+    Examples,
+    /// [`DocstringParagraph::Deprecated`].
+    Deprecated,
+}
+
+/// A `>>>` example of an `Examples` section, as Python's `doctest` sees it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Doctest {
+    /// The source lines without their `>>>` or `...` prompt.
+    pub source: Vec<String>,
+    /// The expected output lines, `<BLANKLINE>` turned into an empty line.
+    pub want: Vec<String>,
+    /// Directives of `# doctest:` comments, e.g. `+SKIP`, in source order.
+    pub options: Vec<String>,
+    /// From the first prompt to the last line of output.
+    pub span: Span,
+}
+
+/// A `.. code-block::` directive, or its `code` and `sourcecode` aliases, or
+/// a Markdown fenced block.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CodeBlock {
+    /// The directive argument or the word after the opening fence.
+    pub language: Option<String>,
+    /// The `:name: value` options of a directive, in order. Flags such as
+    /// `:linenos:` have an empty value.
+    pub options: Vec<(String, String)>,
+    /// The body, dedented, ready to run.
+    pub code: String,
+    /// From the directive or opening fence to the last line of code or the
+    /// closing fence.
+    pub span: Span,
+}
+
+/// What a [`Directive`] is, with the arguments of the known ones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirectiveKind {
+    /// `.. note::`, text on the heading line goes to the content.
+    Note,
+    /// `.. warning::`, text on the heading line goes to the content.
+    Warning,
+    /// `.. math::`, a formula on the heading line goes to the content.
+    Math,
+    /// `.. deprecated:: version`, an explanation may follow the version.
+    Deprecated {
+        /// The version the object was deprecated in.
+        version: String,
+    },
+    /// `.. versionadded:: version`.
+    VersionAdded {
+        /// The version the object was added in.
+        version: String,
+    },
+    /// `.. versionchanged:: version`.
+    VersionChanged {
+        /// The version the object changed in.
+        version: String,
+    },
+    /// `.. image:: uri`.
+    Image {
+        /// The path or URL of the image.
+        uri: String,
+    },
+    /// `.. code-block:: language` and its `code` and `sourcecode` aliases.
+    CodeBlock {
+        /// The language, if given.
+        language: Option<String>,
+    },
+    /// Any other directive, see [`Directive::name`] and [`Directive::argument`].
+    Generic,
+}
+
+/// A reStructuredText directive of a description:
+///
+/// ```text
+/// .. name:: argument
+///    :option: value
+///
+///    content
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    /// The name, e.g. `versionadded`.
+    pub name: String,
+    /// The text after `::`, as written.
+    pub argument: String,
+    /// The typed form of known directives.
+    pub kind: DirectiveKind,
+    /// The `:name: value` field list, in order. Flags have an empty value.
+    pub options: Vec<(String, String)>,
+    /// The indented body, dedented. For directives without arguments, and
+    /// after the version of the version ones, text of the heading line comes
+    /// first.
+    pub content: Vec<String>,
+    /// From the heading to the last line of content.
+    pub span: Span,
+}
+
+/// A node of the inline markup of a description, see [`Argument::inlines`]
+/// and [`Section::inlines`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    /// Text without markup, line breaks included.
+    Text(String),
+    /// ``` ``code`` ```, or `` `code` `` which is a code span in Markdown and
+    /// the default role in reStructuredText.
+    Literal(String),
+    /// `*emphasis*`.
+    Emphasis(Vec<Inline>),
+    /// `**strong**`.
+    Strong(Vec<Inline>),
+    /// A Sphinx role such as ``:func:`paddle.add` `` or ``:math:`x^2` ``.
+    Role {
+        /// The role without its colons, e.g. `func` or `py:meth`.
+        name: String,
+        /// The text between the backquotes.
+        text: String,
+    },
+    /// `` `text <url>`_ ``, `` `name`_ `` or `[text](url)`.
+    Link {
+        /// The link text.
+        text: String,
+        /// The URL, or the reference name when there is none.
+        target: String,
+    },
+    /// A footnote or citation reference, `[1]_`, `[#name]_` or `[CIT2002]_`.
+    FootnoteRef(String),
+}
+
+impl Inline {
+    /// The text of the node without its markup.
+    pub fn plain_text(&self) -> String {
+        match self {
+            Inline::Text(text)
+            | Inline::Literal(text)
+            | Inline::Role { text, .. }
+            | Inline::Link { text, .. }
+            | Inline::FootnoteRef(text) => text.clone(),
+            Inline::Emphasis(children) | Inline::Strong(children) => {
+                children.iter().map(Inline::plain_text).collect()
+            }
+        }
+    }
+}
+
+/// What a [`Block`] is, with its contents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockKind {
+    /// Lines up to a blank line, a list or an indentation change.
+    Paragraph(Vec<Inline>),
+    /// Items marked with `-`, `*` or `+`, the blocks of each item.
+    BulletList(Vec<Vec<Block>>),
+    /// Items marked with `1.`, `1)`, `(1)` or `#.`.
+    OrderedList {
+        /// The number of the first item, `#` counts as 1.
+        start: usize,
+        /// The blocks of each item.
+        items: Vec<Vec<Block>>,
+    },
+    /// Terms each followed by their indented definition.
+    DefinitionList(Vec<DefinitionItem>),
+    /// The indented lines after a paragraph ending with `::`, dedented.
+    LiteralBlock(String),
+    /// Indented blocks.
+    BlockQuote(Vec<Block>),
+}
+
+/// A term of a [`BlockKind::DefinitionList`] and its definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefinitionItem {
+    /// The term line.
+    pub term: Vec<Inline>,
+    /// The indented lines below it.
+    pub definition: Vec<Block>,
+}
+
+/// A block of a description, see [`Argument::blocks`] and [`Section::blocks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// What it is.
+    pub kind: BlockKind,
+    /// From its first to its last line.
+    pub span: Span,
+}
+
+/// What a [`VersionChange`] records.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VersionChangeKind {
+    /// `.. deprecated::` or a `Deprecated` section.
+    Deprecated,
+    /// `.. versionadded::`.
+    Added,
+    /// `.. versionchanged::`.
+    Changed,
+}
+
+/// A `.. deprecated::`, `.. versionadded::` or `.. versionchanged::`
+/// directive, or a `Deprecated` section, see [`Docstring::version_changes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionChange {
+    /// Which directive or section it comes from.
+    pub kind: VersionChangeKind,
+    /// `None` for a `Deprecated` section that does not start with a version.
+    pub version: Option<String>,
+    /// The reason of a deprecation or the note of a change.
+    pub description: Vec<String>,
+    /// The directive or the section.
+    pub span: Span,
+}
+
+impl VersionChange {
+    fn from_directive(directive: &Directive) -> Option<VersionChange> {
+        let (kind, version) = match &directive.kind {
+            DirectiveKind::Deprecated { version } => (VersionChangeKind::Deprecated, version),
+            DirectiveKind::VersionAdded { version } => (VersionChangeKind::Added, version),
+            DirectiveKind::VersionChanged { version } => (VersionChangeKind::Changed, version),
+            _ => return None,
+        };
+        Some(VersionChange {
+            kind,
+            version: Some(version.clone()).filter(|version| !version.is_empty()),
+            description: directive.content.clone(),
+            span: directive.span,
+        })
+    }
+
+    /// A `Deprecated` section, its first word is the version if it looks
+    /// like one, e.g. `2.5` or `v2.5:`.
+    fn from_section(lines: &[String], span: Span) -> VersionChange {
+        let mut lines: Vec<String> = lines
+            .iter()
+            .skip_while(|line| line.trim().is_empty())
+            .cloned()
+            .collect();
+        let mut version = None;
+        if let Some(first) = lines.first_mut() {
+            let trimmed = first.trim();
+            let (word, rest) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+            let word = word.trim_end_matches([':', ',']);
+            let digits = word.strip_prefix('v').unwrap_or(word);
+            if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                version = Some(word.to_string());
+                *first = rest.trim().to_string();
+                if first.is_empty() {
+                    lines.remove(0);
+                }
+            }
+        }
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        VersionChange {
+            kind: VersionChangeKind::Deprecated,
+            version,
+            description: lines,
+            span,
+        }
+    }
+}
+
+/// A part of an `Examples` section, see [`Section::examples`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExampleItem {
+    /// Narrative lines before, between or after doctests.
+    Text {
+        /// The lines, without the blank ones around them.
+        lines: Vec<String>,
+        /// From the first line to the last one.
+        span: Span,
+    },
+    /// A doctest.
+    Doctest(Doctest),
+    /// A code block.
+    CodeBlock(CodeBlock),
+}
+
+/// A section of a docstring and where it was found.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Section {
+    /// The contents.
+    pub paragraph: DocstringParagraph,
+    /// The heading and the body.
+    pub span: Span,
+    /// The heading line, `None` for raw lines.
+    pub heading_span: Option<Span>,
+    /// One span per body line of plain sections, empty for `Args`.
+    pub line_spans: Vec<Span>,
+}
+
+impl From<DocstringParagraph> for Section {
+    fn from(paragraph: DocstringParagraph) -> Section {
+        Section {
+            paragraph,
+            span: Span::default(),
+            heading_span: None,
+            line_spans: vec![],
+        }
+    }
+}
+
+impl Section {
+    /// The paragraphs, lists and other blocks of the text of a section,
+    /// parsed on each call, `None` for `Args` and `Raises`, whose arguments
+    /// have theirs, and for `Examples`.
+    pub fn blocks(&self) -> Option<Vec<Block>> {
+        match &self.paragraph {
+            DocstringParagraph::Args(_)
+            | DocstringParagraph::Raises(_)
+            | DocstringParagraph::Examples(_) => None,
+            paragraph => Some(parse_blocks(&paragraph.lines(), &self.line_spans)),
+        }
+    }
+
+    /// The inline markup of the text of a section, parsed on each call, `None`
+    /// for `Args` and `Raises`, whose arguments have theirs, and for
+    /// `Examples`.
+    pub fn inlines(&self) -> Option<Vec<Inline>> {
+        match &self.paragraph {
+            DocstringParagraph::Args(_)
+            | DocstringParagraph::Raises(_)
+            | DocstringParagraph::Examples(_) => None,
+            paragraph => Some(parse_inline(&paragraph.lines().join("\n"))),
+        }
+    }
+
+    /// The directives of the section, see [`Docstring::directives`].
+    pub fn directives(&self) -> Vec<Directive> {
+        self.texts()
+            .iter()
+            .flat_map(|(lines, spans)| parse_directives(lines, spans))
+            .collect()
+    }
+
+    /// The lines of the section with their spans, one run per argument.
+    fn texts(&self) -> Vec<(Vec<String>, Vec<Span>)> {
+        match &self.paragraph {
+            DocstringParagraph::Args(args) | DocstringParagraph::Raises(args) => args
+                .iter()
+                .map(|arg| (arg.desc.clone(), arg.desc_spans.clone()))
+                .collect(),
+            paragraph => vec![(paragraph.lines(), self.line_spans.clone())],
+        }
+    }
+
+    /// The doctests of an `Examples` section and the text around them,
+    /// `None` for other sections.
+    pub fn examples(&self) -> Option<Vec<ExampleItem>> {
+        match &self.paragraph {
+            DocstringParagraph::Examples(lines) => Some(parse_examples(lines, &self.line_spans)),
+            _ => None,
+        }
+    }
+}
+
+impl DocstringParagraph {
+    /// The text lines of the paragraph, empty for `Args` and `Raises`.
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            DocstringParagraph::Raw(text)
+            | DocstringParagraph::Warning(text)
+            | DocstringParagraph::Note(text) => text.split('\n').map(str::to_string).collect(),
+            DocstringParagraph::Returns(lines)
+            | DocstringParagraph::Examples(lines)
+            | DocstringParagraph::Deprecated(lines) => lines.clone(),
+            DocstringParagraph::Args(_) | DocstringParagraph::Raises(_) => vec![],
+        }
+    }
+
+    /// Which variant this is.
+    pub fn kind(&self) -> SectionKind {
+        match self {
+            DocstringParagraph::Raw(_) => SectionKind::Raw,
+            DocstringParagraph::Warning(_) => SectionKind::Warning,
+            DocstringParagraph::Note(_) => SectionKind::Note,
+            DocstringParagraph::Args(_) => SectionKind::Args,
+            DocstringParagraph::Returns(_) => SectionKind::Returns,
+            DocstringParagraph::Raises(_) => SectionKind::Raises,
+            DocstringParagraph::Examples(_) => SectionKind::Examples,
+            DocstringParagraph::Deprecated(_) => SectionKind::Deprecated,
+        }
+    }
 }
 
-pub type Docstring = Vec<DocstringParagraph>;
+/// Version of the serialized form of [`Docstring`], bumped on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// A parsed docstring, its sections in source order plus lookup tables.
+///
+/// Serializes as `{"schema_version": 2, "sections": [...]}`, each section
+/// tagged with its `kind`. Blocks and inline markup are views derived from the
+/// lines and are not serialized, see [`Section::blocks`] and
+/// [`Section::inlines`] and their [`Argument`] counterparts.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "DocstringRepr", try_from = "DocstringRepr")
+)]
+pub struct Docstring {
+    sections: Vec<Section>,
+    /// Parameter name to the indices of its section and of the argument in it.
+    params: HashMap<String, (usize, usize)>,
+}
+
+impl Docstring {
+    /// Wrap sections in source order, indexing the parameters.
+    pub fn new(sections: Vec<Section>) -> Docstring {
+        let mut params = HashMap::new();
+        for (i, section) in sections.iter().enumerate() {
+            if let DocstringParagraph::Args(args) = &section.paragraph {
+                for (j, arg) in args.iter().enumerate() {
+                    params.entry(arg.name.clone()).or_insert((i, j));
+                }
+            }
+        }
+        Docstring { sections, params }
+    }
+
+    /// All sections, in source order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Number of sections.
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Whether there is no section at all.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Iterate over the sections.
+    pub fn iter(&self) -> std::slice::Iter<'_, Section> {
+        self.sections.iter()
+    }
+
+    /// The first non-blank line before any section.
+    pub fn summary(&self) -> Option<&str> {
+        self.sections
+            .iter()
+            .map_while(|section| match &section.paragraph {
+                DocstringParagraph::Raw(line) => Some(line.trim()),
+                _ => None,
+            })
+            .find(|line| !line.is_empty())
+    }
+
+    /// The blocks of the lines before the first section, summary included.
+    pub fn description(&self) -> Vec<Block> {
+        let (lines, spans): (Vec<String>, Vec<Span>) = self
+            .sections
+            .iter()
+            .map_while(|section| match &section.paragraph {
+                DocstringParagraph::Raw(line) => Some((line.clone(), section.span)),
+                _ => None,
+            })
+            .unzip();
+        parse_blocks(&lines, &spans)
+    }
+
+    /// The sections of a kind, in source order.
+    pub fn sections_of(&self, kind: SectionKind) -> impl Iterator<Item = &Section> {
+        self.sections
+            .iter()
+            .filter(move |section| section.paragraph.kind() == kind)
+    }
+
+    /// Arguments of all `Args` sections, in source order.
+    pub fn params(&self) -> impl Iterator<Item = &Argument> {
+        self.sections
+            .iter()
+            .flat_map(|section| match &section.paragraph {
+                DocstringParagraph::Args(args) => args.as_slice(),
+                _ => &[],
+            })
+    }
+
+    /// Look a parameter up by name, the first one wins if it is documented twice.
+    pub fn param(&self, name: &str) -> Option<&Argument> {
+        let &(i, j) = self.params.get(name)?;
+        match &self.sections[i].paragraph {
+            DocstringParagraph::Args(args) => args.get(j),
+            _ => None,
+        }
+    }
+
+    /// The lines of the first `Returns` section.
+    pub fn returns(&self) -> Option<&[String]> {
+        self.sections_of(SectionKind::Returns)
+            .find_map(|section| match &section.paragraph {
+                DocstringParagraph::Returns(lines) => Some(lines.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// Entries of all `Raises` sections, in source order.
+    pub fn raises(&self) -> impl Iterator<Item = &Argument> {
+        self.sections
+            .iter()
+            .flat_map(|section| match &section.paragraph {
+                DocstringParagraph::Raises(entries) => entries.as_slice(),
+                _ => &[],
+            })
+    }
+
+    /// The lines of the first `Examples` section.
+    pub fn examples(&self) -> Option<&[String]> {
+        self.sections_of(SectionKind::Examples)
+            .find_map(|section| match &section.paragraph {
+                DocstringParagraph::Examples(lines) => Some(lines.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// The doctests of all `Examples` sections, in source order.
+    pub fn doctests(&self) -> Vec<Doctest> {
+        self.sections_of(SectionKind::Examples)
+            .flat_map(|section| section.examples().unwrap_or_default())
+            .filter_map(|item| match item {
+                ExampleItem::Doctest(doctest) => Some(doctest),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The code blocks of every section, argument descriptions included, in
+    /// source order.
+    pub fn code_blocks(&self) -> Vec<CodeBlock> {
+        self.texts()
+            .iter()
+            .flat_map(|(lines, spans)| parse_examples(lines, spans))
+            .filter_map(|item| match item {
+                ExampleItem::CodeBlock(block) => Some(block),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The directives of every section, argument descriptions included, in
+    /// source order.
+    pub fn directives(&self) -> Vec<Directive> {
+        self.texts()
+            .iter()
+            .flat_map(|(lines, spans)| parse_directives(lines, spans))
+            .collect()
+    }
+
+    /// The deprecations, additions and changes of the directives of every
+    /// section and of the `Deprecated` sections, in source order.
+    pub fn version_changes(&self) -> Vec<VersionChange> {
+        let mut changes: Vec<VersionChange> = self
+            .directives()
+            .iter()
+            .filter_map(VersionChange::from_directive)
+            .collect();
+        changes.extend(
+            self.sections_of(SectionKind::Deprecated).map(|section| {
+                VersionChange::from_section(&section.paragraph.lines(), section.span)
+            }),
+        );
+        changes.sort_by_key(|change| change.span.start);
+        changes
+    }
+
+    /// The first deprecation, see [`Docstring::version_changes`].
+    pub fn deprecation(&self) -> Option<VersionChange> {
+        self.version_changes()
+            .into_iter()
+            .find(|change| change.kind == VersionChangeKind::Deprecated)
+    }
+
+    /// The runs of text lines with their spans: the lines outside of any
+    /// section, grouped, then the texts of each section.
+    fn texts(&self) -> Vec<(Vec<String>, Vec<Span>)> {
+        let mut texts = vec![];
+        let mut raw: (Vec<String>, Vec<Span>) = (vec![], vec![]);
+        for section in &self.sections {
+            if let DocstringParagraph::Raw(line) = &section.paragraph {
+                raw.0.push(line.clone());
+                raw.1.push(section.span);
+                continue;
+            }
+            if !raw.0.is_empty() {
+                texts.push(std::mem::take(&mut raw));
+            }
+            texts.extend(section.texts());
+        }
+        if !raw.0.is_empty() {
+            texts.push(raw);
+        }
+        texts
+    }
+}
+
+/// A parsed docstring, its sections in source order. Blocks and inline
+/// markup are derived from the lines when asked for and are not serialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+struct DocstringRepr {
+    schema_version: u32,
+    sections: Vec<Section>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Docstring> for DocstringRepr {
+    fn from(docstring: Docstring) -> DocstringRepr {
+        DocstringRepr {
+            schema_version: SCHEMA_VERSION,
+            sections: docstring.sections,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<DocstringRepr> for Docstring {
+    type Error = String;
+
+    fn try_from(repr: DocstringRepr) -> Result<Docstring, String> {
+        if repr.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "unsupported schema version {}, expected {}",
+                repr.schema_version, SCHEMA_VERSION
+            ));
+        }
+        Ok(Docstring::new(repr.sections))
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for Docstring {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Docstring".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut schema = DocstringRepr::json_schema(generator);
+        if let Some(version) = schema.pointer_mut("/properties/schema_version") {
+            version["const"] = SCHEMA_VERSION.into();
+        }
+        schema
+    }
+}
+
+/// The JSON Schema (draft 2020-12) of the serialized [`Docstring`].
+#[cfg(feature = "json-schema")]
+pub fn json_schema() -> schemars::Schema {
+    schemars::generate::SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<Docstring>()
+}
+
+impl From<Vec<Section>> for Docstring {
+    fn from(sections: Vec<Section>) -> Docstring {
+        Docstring::new(sections)
+    }
+}
+
+impl FromIterator<Section> for Docstring {
+    fn from_iter<I: IntoIterator<Item = Section>>(iter: I) -> Docstring {
+        Docstring::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Docstring {
+    type Item = Section;
+    type IntoIter = std::vec::IntoIter<Section>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.sections.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Docstring {
+    type Item = &'a Section;
+    type IntoIter = std::slice::Iter<'a, Section>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.sections.iter()
+    }
+}
+
+/// Drop the spans, for tests comparing parsed values with ones written by
+/// hand.
+#[cfg(test)]
+pub(crate) trait WithoutSpans {
+    fn without_spans(self) -> Self;
+}
+
+#[cfg(test)]
+impl WithoutSpans for Argument {
+    fn without_spans(self) -> Argument {
+        Argument {
+            name: self.name,
+            r#type: self.r#type,
+            desc: self.desc,
+            ..Argument::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl WithoutSpans for DocstringParagraph {
+    fn without_spans(self) -> DocstringParagraph {
+        match self {
+            DocstringParagraph::Args(args) => DocstringParagraph::Args(args.without_spans()),
+            DocstringParagraph::Raises(args) => DocstringParagraph::Raises(args.without_spans()),
+            paragraph => paragraph,
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T: WithoutSpans> WithoutSpans for Vec<T> {
+    fn without_spans(self) -> Vec<T> {
+        self.into_iter().map(WithoutSpans::without_spans).collect()
+    }
+}
+
+#[cfg(test)]
+impl<T: WithoutSpans, E> WithoutSpans for Result<T, E> {
+    fn without_spans(self) -> Result<T, E> {
+        self.map(WithoutSpans::without_spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arg(name: &str) -> Argument {
+        Argument {
+            name: name.to_string(),
+            desc: vec![format!("Description of {}", name)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let docstring = Docstring::new(vec![
+            DocstringParagraph::Raw("".to_string()).into(),
+            DocstringParagraph::Raw("Summary.".to_string()).into(),
+            DocstringParagraph::Args(vec![arg("a"), arg("b")]).into(),
+            DocstringParagraph::Raises(vec![arg("ValueError")]).into(),
+            DocstringParagraph::Raw("Trailing.".to_string()).into(),
+            DocstringParagraph::Args(vec![
+                arg("c"),
+                Argument {
+                    desc: vec!["Documented twice".to_string()],
+                    ..arg("a")
+                },
+            ])
+            .into(),
+            DocstringParagraph::Returns(vec!["Nothing".to_string()]).into(),
+        ]);
+        assert_eq!(docstring.summary(), Some("Summary."));
+        let names: Vec<&str> = docstring.params().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "a"]);
+        assert_eq!(docstring.param("c"), Some(&arg("c")));
+        assert_eq!(docstring.param("a"), Some(&arg("a")));
+        assert_eq!(docstring.param("d"), None);
+        assert_eq!(docstring.raises().count(), 1);
+        assert_eq!(docstring.returns(), Some(&["Nothing".to_string()][..]));
+        assert_eq!(docstring.examples(), None);
+        assert_eq!(docstring.sections_of(SectionKind::Args).count(), 2);
+        assert_eq!(docstring.sections_of(SectionKind::Raw).count(), 3);
+    }
+
+    #[test]
+    fn test_doctests() {
+        let doc = "Summary.\n\nExamples:\n    Sum:\n\n    >>> 1 + 1\n    2\n";
+        let docstring = crate::parse(doc).unwrap();
+        let doctests = docstring.doctests();
+        assert_eq!(doctests.len(), 1);
+        assert_eq!(doctests[0].source, ["1 + 1"]);
+        assert_eq!(doctests[0].want, ["2"]);
+        assert_eq!(doctests[0].span.start, doc.find(">>>").unwrap());
+        assert_eq!(doctests[0].span.end, doc.len() - 1);
+        let examples = docstring.sections_of(SectionKind::Examples).next().unwrap();
+        assert_eq!(examples.examples().unwrap().len(), 2);
+        assert_eq!(docstring.sections()[0].examples(), None);
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let doc = "Summary.
+
+.. code-block:: python
+
+    setup()
+
+Args:
+    x: The x, e.g.
+        ```python
+        f(x=1)
+        ```
+Examples:
+    .. code:: python
+       :name: example
+
+       f(x=2)
+";
+        let docstring = crate::parse(doc).unwrap();
+        let blocks = docstring.code_blocks();
+        let code: Vec<&str> = blocks.iter().map(|block| block.code.as_str()).collect();
+        assert_eq!(code, ["setup()", "f(x=1)", "f(x=2)"]);
+        assert_eq!(blocks[0].span.start, doc.find(".. code-block").unwrap());
+        assert_eq!(blocks[0].span.end, doc.find("setup()").unwrap() + 7);
+        assert_eq!(blocks[1].span.start, doc.find("```").unwrap());
+        assert_eq!(
+            blocks[2].options,
+            [("name".to_string(), "example".to_string())]
+        );
+        assert_eq!(blocks[2].span.start_pos.line, 13);
+    }
+
+    #[test]
+    fn test_directives() {
+        let doc = "
+Summary.
+
+.. deprecated:: 2.5
+   Use g instead.
+
+Args:
+    x: The x.
+        .. versionadded:: 2.6
+";
+        let docstring = crate::parse(doc).unwrap();
+        let directives = docstring.directives();
+        assert_eq!(directives.len(), 2);
+        assert_eq!(
+            directives[0].kind,
+            DirectiveKind::Deprecated {
+                version: "2.5".to_string()
+            }
+        );
+        assert_eq!(directives[0].content, ["Use g instead."]);
+        assert_eq!(directives[0].span.start, doc.find(".. deprecated").unwrap());
+        assert_eq!(directives[0].span.end, doc.find("instead.").unwrap() + 8);
+        let args = docstring
+            .sections
+            .iter()
+            .find_map(|section| match &section.paragraph {
+                DocstringParagraph::Args(args) => Some(args),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(args[0].directives(), directives[1..]);
+        assert_eq!(directives[1].span.start_pos.line, 9);
+    }
+
+    #[test]
+    fn test_blocks() {
+        let doc = "
+Summary.
+
+Details::
+
+    raw text
+
+Args:
+    mode: One of
+        - sum, the total.
+        - mean.
+Returns:
+    The result.
+";
+        let docstring = crate::parse(doc).unwrap();
+        let kinds = |blocks: &[Block]| -> Vec<&'static str> {
+            blocks
+                .iter()
+                .map(|block| match block.kind {
+                    BlockKind::Paragraph(_) => "paragraph",
+                    BlockKind::BulletList(_) => "bullet_list",
+                    BlockKind::LiteralBlock(_) => "literal_block",
+                    _ => "other",
+                })
+                .collect()
+        };
+        let description = docstring.description();
+        assert_eq!(
+            kinds(&description),
+            ["paragraph", "paragraph", "literal_block"]
+        );
+        assert_eq!(
+            description[2].kind,
+            BlockKind::LiteralBlock("raw text".to_string())
+        );
+        assert_eq!(description[2].span.start, doc.find("    raw").unwrap());
+        let blocks = docstring.param("mode").unwrap().blocks();
+        assert_eq!(kinds(&blocks), ["paragraph", "bullet_list"]);
+        assert_eq!(blocks[1].span.start_pos.line, 10);
+        assert_eq!(blocks[1].span.end_pos.line, 11);
+        let returns = docstring.sections_of(SectionKind::Returns).next().unwrap();
+        assert_eq!(kinds(&returns.blocks().unwrap()), ["paragraph"]);
+    }
+
+    #[test]
+    fn test_inlines() {
+        let doc = "
+Uses ``x``.
+
+Args:
+    x: A :class:`Tensor`,
+        see *below*.
+Examples:
+    >>> f(*args)
+";
+        let docstring = crate::parse(doc).unwrap();
+        assert_eq!(
+            docstring.sections()[0].inlines(),
+            Some(vec![
+                Inline::Text("Uses ".to_string()),
+                Inline::Literal("x".to_string()),
+                Inline::Text(".".to_string()),
+            ])
+        );
+        let inlines = docstring.param("x").unwrap().inlines();
+        assert_eq!(
+            inlines[1],
+            Inline::Role {
+                name: "class".to_string(),
+                text: "Tensor".to_string()
+            }
+        );
+        assert_eq!(inlines[3].plain_text(), "below");
+        let examples = docstring.sections_of(SectionKind::Examples).next().unwrap();
+        assert_eq!(examples.inlines(), None);
+    }
+
+    #[test]
+    fn test_version_changes() {
+        let doc = "
+Summary.
+
+.. versionadded:: 1.0
+
+Deprecated:
+    v2.5: Use g instead,
+    f is slow.
+
+Args:
+    x: The x.
+        .. versionchanged:: 2.0 Accepts floats.
+";
+        let docstring = crate::parse(doc).unwrap();
+        let changes = docstring.version_changes();
+        let summary: Vec<(VersionChangeKind, Option<&str>)> = changes
+            .iter()
+            .map(|change| (change.kind, change.version.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (VersionChangeKind::Added, Some("1.0")),
+                (VersionChangeKind::Deprecated, Some("v2.5")),
+                (VersionChangeKind::Changed, Some("2.0")),
+            ]
+        );
+        assert_eq!(changes[1].description, ["Use g instead,", "f is slow."]);
+        assert_eq!(changes[1].span.start, doc.find("Deprecated:").unwrap());
+        assert_eq!(changes[2].description, ["Accepts floats."]);
+        assert_eq!(docstring.deprecation(), Some(changes[1].clone()));
+
+        let docstring = crate::parse("Summary.\n\nDeprecated:\n    Use g instead.").unwrap();
+        let deprecation = docstring.deprecation().unwrap();
+        assert_eq!(deprecation.version, None);
+        assert_eq!(deprecation.description, ["Use g instead."]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let docstring = Docstring::new(vec![
+            DocstringParagraph::Raw("Summary.".to_string()).into(),
+            DocstringParagraph::Args(vec![arg("a")]).into(),
+        ]);
+        let json = serde_json::to_value(&docstring).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["sections"][0]["paragraph"]["kind"], "raw");
+        assert_eq!(json["sections"][0]["paragraph"]["value"], "Summary.");
+        assert_eq!(json["sections"][1]["paragraph"]["kind"], "args");
+        assert_eq!(json["sections"][1]["paragraph"]["value"][0]["name"], "a");
+        assert_eq!(
+            json["sections"][1]["paragraph"]["value"][0]["type"],
+            serde_json::Value::Null
+        );
+        let back: Docstring = serde_json::from_value(json).unwrap();
+        assert_eq!(back, docstring);
+        assert_eq!(back.param("a"), Some(&arg("a")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_schema_version() {
+        let err = serde_json::from_str::<Docstring>(r#"{"schema_version": 1, "sections": []}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported schema version 1"));
+    }
+
+    /// Regenerate with `UPDATE_SCHEMA=1 cargo test --features json-schema`.
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_json_schema_is_published() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../schema/docstring.schema.json"
+        );
+        let generated = serde_json::to_string_pretty(&json_schema()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_SCHEMA").is_some() {
+            std::fs::write(path, &generated).unwrap();
+        }
+        let published = std::fs::read_to_string(path).unwrap();
+        assert_eq!(generated, published, "the published schema is out of date");
+        let schema = json_schema();
+        assert_eq!(
+            schema.get("$schema").and_then(|v| v.as_str()),
+            Some("https://json-schema.org/draft/2020-12/schema")
+        );
+    }
+}
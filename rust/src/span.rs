@@ -0,0 +1,129 @@
+//! Locations in the input.
+
+use crate::utils::line_ranges;
+
+/// A location in the input, `line` is 1-based and `col` counts characters
+/// from 0, so it indexes a Python `str` line. Python's `ast` module counts
+/// UTF-8 bytes in `col_offset` instead, use [`Span::start`] and
+/// [`Span::end`] for byte offsets.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Position {
+    /// Line number, from 1.
+    pub line: usize,
+    /// Column in characters, from 0.
+    pub col: usize,
+}
+
+/// A byte range of the input together with its line/column positions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset past the last character.
+    pub end: usize,
+    /// Position of `start`.
+    pub start_pos: Position,
+    /// Position of `end`.
+    pub end_pos: Position,
+}
+
+impl Span {
+    /// From the start of `self` to the end of `last`.
+    pub(crate) fn to(&self, last: &Span) -> Span {
+        Span {
+            start: self.start,
+            end: last.end,
+            start_pos: self.start_pos,
+            end_pos: last.end_pos,
+        }
+    }
+}
+
+/// Maps byte offsets of an input to line/column positions.
+pub struct LineIndex<'a> {
+    input: &'a str,
+    line_starts: Vec<usize>,
+    /// Offsets in `input` of the bytes of the text actually parsed, when it
+    /// is a normalized version of `input`.
+    offsets: Option<&'a [usize]>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the line starts of `input`.
+    pub fn new(input: &'a str) -> LineIndex<'a> {
+        let line_starts = line_ranges(input)
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect();
+        LineIndex {
+            input,
+            line_starts,
+            offsets: None,
+        }
+    }
+
+    /// Index `input` for offsets of a text derived from it, `offsets` giving
+    /// the offset in `input` of each of its bytes and of its end.
+    pub(crate) fn mapped(input: &'a str, offsets: &'a [usize]) -> LineIndex<'a> {
+        LineIndex {
+            offsets: Some(offsets),
+            ..LineIndex::new(input)
+        }
+    }
+
+    fn origin(&self, offset: usize) -> usize {
+        match self.offsets {
+            Some(offsets) => offsets[offset.min(offsets.len() - 1)],
+            None => offset,
+        }
+    }
+
+    /// The position of a byte offset.
+    pub fn position(&self, offset: usize) -> Position {
+        self.input_position(self.origin(offset))
+    }
+
+    fn input_position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let col = self.input[line_start..offset.min(self.input.len())]
+            .chars()
+            .count();
+        Position {
+            line: line + 1,
+            col,
+        }
+    }
+
+    /// The span of a byte range.
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (start, end) = (self.origin(start), self.origin(end));
+        Span {
+            start,
+            end,
+            start_pos: self.input_position(start),
+            end_pos: self.input_position(end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let index = LineIndex::new("ab\ncdé\n\nf");
+        assert_eq!(index.position(0), Position { line: 1, col: 0 });
+        assert_eq!(index.position(2), Position { line: 1, col: 2 });
+        assert_eq!(index.position(3), Position { line: 2, col: 0 });
+        assert_eq!(index.position(7), Position { line: 2, col: 3 });
+        assert_eq!(index.position(8), Position { line: 3, col: 0 });
+        assert_eq!(index.position(9), Position { line: 4, col: 0 });
+        assert_eq!(index.position(10), Position { line: 4, col: 1 });
+    }
+}
@@ -1,41 +1,158 @@
-static DEFAULT_TAB_SIZE: usize = 8;
-static MAX_SIZE: usize = std::usize::MAX;
+use crate::indent::{advance, DEFAULT_TAB_SIZE};
+use crate::span::LineIndex;
 
-pub fn expandtabs(s: &str, tab_size: Option<usize>) -> String {
-    let tab_size = tab_size.unwrap_or(DEFAULT_TAB_SIZE);
-    s.replace('\t', " ".repeat(tab_size).as_str())
-    // result
-}
+static MAX_SIZE: usize = usize::MAX;
 
+/// Strip the indentation of a docstring as Python's `inspect.cleandoc` does.
+///
+/// Tabs are expanded to tab stops every `tab_size` columns first, 4 by default
+/// as when parsing where `inspect.cleandoc` uses 8, and `\r\n` and `\r` line
+/// breaks become `\n`.
 pub fn cleandoc(doc: &str, tab_size: Option<usize>) -> String {
-    let doc = expandtabs(doc, tab_size);
-    let mut lines: Vec<_> = doc.split('\n').collect();
-    // Find minimum indentation of any non-blank lines after first line.
-    let mut margin = MAX_SIZE;
-    for line in lines.iter().skip(1) {
-        let content = line.trim_start().len();
-        if content > 0 {
-            let indent_size = line.len() - content;
-            margin = margin.min(indent_size);
+    Normalized::new(doc, tab_size.unwrap_or(DEFAULT_TAB_SIZE), false).text
+}
+
+/// Byte ranges of the lines of `input` without their line break, which is
+/// `\n`, `\r\n` or a lone `\r`.
+pub fn line_ranges(input: &str) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let bytes = input.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                ranges.push((start, i));
+                start = i + 1;
+            }
+            b'\r' => {
+                ranges.push((start, i));
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                start = i + 1;
+            }
+            _ => {}
         }
+        i += 1;
     }
-    // Remove indentation.
-    if !lines.is_empty() {
-        lines[0] = lines[0].trim_start();
+    ranges.push((start, input.len()));
+    ranges
+}
+
+/// Turn every line break into `\n` and, with a `tab_size`, expand tabs.
+/// Also returns the offset in `original` of every byte of the result, then of
+/// its end; a line break comes from its first byte and the spaces of a tab
+/// all come from the tab.
+fn unify(original: &str, tab_size: Option<usize>) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(original.len());
+    let mut origin = Vec::with_capacity(original.len() + 1);
+    let mut col = 0;
+    let mut chars = original.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (c, tab_size) {
+            ('\r', _) => {
+                chars.next_if(|&(_, c)| c == '\n');
+                text.push('\n');
+                origin.push(i);
+            }
+            ('\t', Some(tab_size)) => {
+                let width = advance(col, c, tab_size) - col;
+                text.extend(std::iter::repeat_n(' ', width));
+                origin.extend(std::iter::repeat_n(i, width));
+            }
+            _ => {
+                text.push(c);
+                origin.extend(std::iter::repeat_n(i, c.len_utf8()));
+            }
+        }
+        col = match c {
+            '\r' | '\n' => 0,
+            _ => advance(col, c, tab_size.unwrap_or(1)),
+        };
     }
-    if margin < MAX_SIZE {
-        for line in lines.iter_mut().skip(1) {
-            *line = &line[margin.min(line.len())..];
+    origin.push(original.len());
+    (text, origin)
+}
+
+/// A docstring trimmed like [`cleandoc`], remembering where each byte of the
+/// trimmed text comes from so spans can point into the original one.
+pub struct Normalized<'a> {
+    pub original: &'a str,
+    pub text: String,
+    /// Offset in `original` of every byte of `text`, then of its end.
+    offsets: Vec<usize>,
+}
+
+impl<'a> Normalized<'a> {
+    /// With `first_line`, the first line counts towards the margin as well,
+    /// so that the body of a section it opens keeps its indentation.
+    pub fn new(original: &'a str, tab_size: usize, first_line: bool) -> Normalized<'a> {
+        let (expanded, origin) = unify(original, Some(tab_size));
+        let mut lines = line_ranges(&expanded);
+
+        // Find minimum indentation of any non-blank lines after first line.
+        let mut margin = MAX_SIZE;
+        for &(start, end) in lines.iter().skip(usize::from(!first_line)) {
+            let line = &expanded[start..end];
+            let content = line.trim_start().len();
+            if content > 0 {
+                margin = margin.min(line.len() - content);
+            }
+        }
+        // Remove indentation.
+        let (start, end) = lines[0];
+        lines[0].0 = end - expanded[start..end].trim_start().len();
+        if margin < MAX_SIZE {
+            for (start, end) in lines.iter_mut().skip(1) {
+                *start += margin.min(*end - *start);
+            }
+        }
+        // Remove any trailing or leading blank lines.
+        let blank = |&(start, end): &(usize, usize)| expanded[start..end].trim().is_empty();
+        while lines.last().is_some_and(blank) {
+            lines.pop();
+        }
+        let leading = lines.iter().take_while(|line| blank(line)).count();
+        lines.drain(..leading);
+
+        let mut text = String::with_capacity(expanded.len());
+        let mut offsets = Vec::with_capacity(expanded.len() + 1);
+        for (i, &(start, end)) in lines.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+                offsets.push(origin[lines[i - 1].1]);
+            }
+            text.push_str(&expanded[start..end]);
+            offsets.extend_from_slice(&origin[start..end]);
+        }
+        offsets.push(lines.last().map_or(0, |&(_, end)| origin[end]));
+        Normalized {
+            original,
+            text,
+            offsets,
         }
     }
-    // Remove any trailing or leading blank lines.
-    while !lines.is_empty() && lines.last().unwrap().is_empty() {
-        lines.pop();
+
+    /// The original text with only its line breaks turned into `\n`.
+    pub fn line_breaks(original: &'a str) -> Normalized<'a> {
+        let (text, offsets) = unify(original, None);
+        Normalized {
+            original,
+            text,
+            offsets,
+        }
     }
-    while !lines.is_empty() && lines.first().unwrap().is_empty() {
-        lines.remove(0);
+
+    /// The offset in the original text of an offset in the trimmed one.
+    pub fn offset(&self, pos: usize) -> usize {
+        self.offsets[pos.min(self.offsets.len() - 1)]
+    }
+
+    /// Index of the trimmed text giving positions in the original one.
+    pub fn index(&self) -> LineIndex<'_> {
+        LineIndex::mapped(self.original, &self.offsets)
     }
-    lines.join("\n")
 }
 
 #[cfg(test)]
@@ -122,4 +239,50 @@ mod tests {
             "The first part\n  With indent\nThe second part"
         );
     }
+
+    #[test]
+    fn test_cleandoc_default_tab_size() {
+        let doc = "Summary.\n\tx\n\t\ty";
+        assert_eq!(cleandoc(doc, None), "Summary.\nx\n    y");
+        assert_eq!(cleandoc(doc, Some(8)), "Summary.\nx\n        y");
+    }
+
+    #[test]
+    fn test_normalized_offsets() {
+        let doc = "Summary.\n\n\tArgs:\n\t    x: The x.\n\t";
+        let normalized = Normalized::new(doc, 4, false);
+        assert_eq!(normalized.text, "Summary.\n\nArgs:\n    x: The x.");
+        let x = normalized.text.find('x').unwrap();
+        assert_eq!(normalized.offset(x), doc.find('x').unwrap());
+        assert_eq!(normalized.offset(normalized.text.len()), doc.len() - 2);
+        let position = normalized.index().position(x);
+        assert_eq!((position.line, position.col), (4, 5));
+
+        let doc = "\n    Summary.\n    ";
+        let normalized = Normalized::new(doc, 4, false);
+        assert_eq!(normalized.text, "Summary.");
+        assert_eq!(normalized.offset(0), 5);
+        assert_eq!(normalized.offset(8), 13);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(
+            line_ranges("a\r\nb\rc\n"),
+            vec![(0, 1), (3, 4), (5, 6), (7, 7)]
+        );
+        let doc = "Summary.\r\n\r\n    Args:\r        x: The x.\r\n    ";
+        let normalized = Normalized::new(doc, 4, false);
+        assert_eq!(normalized.text, "Summary.\n\nArgs:\n    x: The x.");
+        assert_eq!(normalized.offset(8), 8);
+        let x = normalized.text.find('x').unwrap();
+        assert_eq!(normalized.offset(x), doc.find('x').unwrap());
+        let normalized = Normalized::line_breaks(doc);
+        assert_eq!(
+            normalized.text,
+            doc.replace("\r\n", "\n").replace('\r', "\n")
+        );
+        assert_eq!(normalized.offset(9), 10);
+        assert_eq!(normalized.offset(10), 12);
+    }
 }
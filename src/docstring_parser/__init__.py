@@ -1 +1,21 @@
-from docstring_parser._core import parse
+from docstring_parser._core import (
+    SCHEMA_VERSION,
+    CstNode,
+    CstToken,
+    DocstringParagraphType,
+    DocstringParseError,
+    InvalidValueError,
+    PyBlock,
+    PyCodeBlock,
+    PyDirective,
+    PyDocstring,
+    PyDoctest,
+    PyInline,
+    PyVersionChange,
+    UnexpectedEndError,
+    format_docstring,
+    json_schema,
+    parse,
+    parse_cst,
+    parse_with_diagnostics,
+)
@@ -0,0 +1,357 @@
+"""Compatibility layer with the API of the ``docstring_parser`` PyPI package.
+
+The objects mirror the ones of the pure Python library so existing call sites
+keep working, while the parsing itself is done by the Rust parser::
+
+    from docstring_parser.compat import parse
+
+    doc = parse(func.__doc__)
+    doc.params[0].arg_name
+
+This is not a drop-in replacement for the package: it lives under
+``docstring_parser.compat`` rather than ``docstring_parser``, and only the Google
+style is supported. ``DocstringStyle.AUTO`` resolves to it and the other styles
+raise ``ParseError``.
+
+Lines outside of any section that follow a section are appended to the long
+description. ``Note:``, ``Warning:`` and ``Examples:`` sections become
+``DocstringMeta`` entries keyed ``note``, ``warning`` and ``examples``.
+"""
+
+import enum
+import re
+from typing import List, Optional
+
+from docstring_parser._core import DocstringParagraphType, DocstringParseError
+from docstring_parser._core import parse as _parse
+
+ParseError = DocstringParseError
+
+PARAM_KEYWORDS = {"param", "parameter", "arg", "argument", "attribute", "key", "keyword"}
+RAISES_KEYWORDS = {"raises", "raise", "except", "exception"}
+DEPRECATION_KEYWORDS = {"deprecation", "deprecated"}
+RETURNS_KEYWORDS = {"return", "returns"}
+YIELDS_KEYWORDS = {"yield", "yields"}
+
+_DEFAULT_RE = re.compile(r"\bdefaults? to (.+?)\.?\s*$", re.IGNORECASE)
+_OPTIONAL_SUFFIX = ", optional"
+_META_KEYS = {
+    DocstringParagraphType.Note: "note",
+    DocstringParagraphType.Warning: "warning",
+    DocstringParagraphType.Examples: "examples",
+}
+_META_TITLES = {"note": "Note", "warning": "Warning", "examples": "Examples"}
+_SECTIONS = ["args", "returns", "raises", "examples", "notes", "warnings", "deprecated"]
+_ALIASES = {"Note": "notes", "Warning": "warnings"}
+
+
+class DocstringStyle(enum.Enum):
+    REST = 1
+    GOOGLE = 2
+    NUMPYDOC = 3
+    EPYDOC = 4
+    AUTO = 255
+
+
+class RenderingStyle(enum.Enum):
+    COMPACT = 1
+    CLEAN = 2
+    EXPANDED = 3
+
+
+class DocstringMeta:
+    def __init__(self, args: List[str], description: Optional[str]) -> None:
+        self.args = args
+        self.description = description
+
+
+class DocstringParam(DocstringMeta):
+    def __init__(
+        self,
+        args: List[str],
+        description: Optional[str],
+        arg_name: str,
+        type_name: Optional[str],
+        is_optional: Optional[bool],
+        default: Optional[str],
+    ) -> None:
+        super().__init__(args, description)
+        self.arg_name = arg_name
+        self.type_name = type_name
+        self.is_optional = is_optional
+        self.default = default
+
+
+class DocstringReturns(DocstringMeta):
+    def __init__(
+        self,
+        args: List[str],
+        description: Optional[str],
+        type_name: Optional[str],
+        is_generator: bool,
+        return_name: Optional[str] = None,
+    ) -> None:
+        super().__init__(args, description)
+        self.type_name = type_name
+        self.is_generator = is_generator
+        self.return_name = return_name
+
+
+class DocstringRaises(DocstringMeta):
+    def __init__(
+        self,
+        args: List[str],
+        description: Optional[str],
+        type_name: Optional[str],
+    ) -> None:
+        super().__init__(args, description)
+        self.type_name = type_name
+
+
+class DocstringDeprecated(DocstringMeta):
+    def __init__(
+        self,
+        args: List[str],
+        description: Optional[str],
+        version: Optional[str],
+    ) -> None:
+        super().__init__(args, description)
+        self.version = version
+
+
+class Docstring:
+    def __init__(self, style: Optional[DocstringStyle] = None) -> None:
+        self.short_description: Optional[str] = None
+        self.long_description: Optional[str] = None
+        self.blank_after_short_description = False
+        self.blank_after_long_description = False
+        self.meta: List[DocstringMeta] = []
+        self.style = style
+
+    @property
+    def description(self) -> Optional[str]:
+        parts = [
+            part
+            for part in (self.short_description, self.long_description)
+            if part is not None
+        ]
+        if not parts:
+            return None
+        separator = "\n\n" if self.blank_after_short_description else "\n"
+        return separator.join(parts)
+
+    @property
+    def params(self) -> List[DocstringParam]:
+        return [item for item in self.meta if isinstance(item, DocstringParam)]
+
+    @property
+    def raises(self) -> List[DocstringRaises]:
+        return [item for item in self.meta if isinstance(item, DocstringRaises)]
+
+    @property
+    def returns(self) -> Optional[DocstringReturns]:
+        for item in self.meta:
+            if isinstance(item, DocstringReturns):
+                return item
+        return None
+
+    @property
+    def many_returns(self) -> List[DocstringReturns]:
+        return [item for item in self.meta if isinstance(item, DocstringReturns)]
+
+    @property
+    def deprecation(self) -> Optional[DocstringDeprecated]:
+        for item in self.meta:
+            if isinstance(item, DocstringDeprecated):
+                return item
+        return None
+
+
+def _description(lines: List[str]) -> Optional[str]:
+    text = "\n".join(lines).strip()
+    return text or None
+
+
+def _param(arg) -> DocstringParam:
+    type_name = arg.type
+    is_optional = None
+    if type_name is not None:
+        type_name = type_name.strip()
+        is_optional = type_name.endswith(_OPTIONAL_SUFFIX)
+        if is_optional:
+            type_name = type_name[: -len(_OPTIONAL_SUFFIX)]
+    head = arg.name if arg.type is None else "{} ({})".format(arg.name, arg.type)
+    description = _description(arg.lines)
+    default = None
+    if description is not None:
+        match = _DEFAULT_RE.search(description)
+        if match:
+            default = match.group(1)
+    return DocstringParam(
+        args=["param", head],
+        description=description,
+        arg_name=arg.name,
+        type_name=type_name,
+        is_optional=is_optional,
+        default=default,
+    )
+
+
+def _returns(lines: List[str]) -> DocstringReturns:
+    text = "\n".join(lines).strip()
+    first = text.split("\n", 1)[0]
+    if ":" not in first:
+        return DocstringReturns(
+            args=["returns"],
+            description=text or None,
+            type_name=None,
+            is_generator=False,
+        )
+    type_name, description = text.split(":", 1)
+    return DocstringReturns(
+        args=["returns", type_name.strip()],
+        description=description.strip() or None,
+        type_name=type_name.strip(),
+        is_generator=False,
+    )
+
+
+def _raises(entry) -> DocstringRaises:
+    return DocstringRaises(
+        args=["raises", entry.name],
+        description=_description(entry.lines),
+        type_name=entry.name,
+    )
+
+
+def _deprecation(change) -> DocstringDeprecated:
+    return DocstringDeprecated(
+        args=["deprecation"],
+        description=change.description or None,
+        version=change.version,
+    )
+
+
+def _set_descriptions(docstring: Docstring, lines: List[str]) -> None:
+    while lines and not lines[0].strip():
+        lines = lines[1:]
+    if not lines:
+        return
+    docstring.short_description = lines[0].strip()
+    rest = lines[1:]
+    docstring.blank_after_short_description = bool(rest) and not rest[0].strip()
+    docstring.long_description = _description(rest)
+    docstring.blank_after_long_description = (
+        docstring.long_description is not None and not rest[-1].strip()
+    )
+
+
+def parse(text: Optional[str], style: DocstringStyle = DocstringStyle.AUTO) -> Docstring:
+    """Parse a docstring into the object model of the ``docstring_parser`` package."""
+    if style not in (DocstringStyle.AUTO, DocstringStyle.GOOGLE):
+        raise ParseError("unsupported docstring style: {}".format(style.name))
+    docstring = Docstring(style=DocstringStyle.GOOGLE)
+    if not text:
+        return docstring
+
+    parsed = _parse(text, sections=_SECTIONS, aliases=_ALIASES)
+    description = []
+    trailing = None
+    for paragraph in parsed:
+        kind = paragraph.type
+        if kind == DocstringParagraphType.Raw:
+            (description if trailing is None else trailing).extend(paragraph.lines)
+            continue
+        if trailing is None:
+            trailing = []
+        if kind == DocstringParagraphType.Args:
+            docstring.meta.extend(_param(arg) for arg in paragraph.args)
+        elif kind == DocstringParagraphType.Raises:
+            docstring.meta.extend(_raises(entry) for entry in paragraph.args)
+        elif kind == DocstringParagraphType.Returns:
+            docstring.meta.append(_returns(paragraph.lines))
+        elif kind in _META_KEYS:
+            docstring.meta.append(
+                DocstringMeta(
+                    args=[_META_KEYS[kind]], description=_description(paragraph.lines)
+                )
+            )
+    deprecation = parsed.deprecation
+    if deprecation is not None:
+        docstring.meta.append(_deprecation(deprecation))
+    if trailing and any(line.strip() for line in trailing):
+        description = description + [""] + trailing
+    _set_descriptions(docstring, description)
+    return docstring
+
+
+def _indented(description: Optional[str], indent: str) -> List[str]:
+    lines = (description or "").splitlines() or [""]
+    return [lines[0]] + [indent + line if line else "" for line in lines[1:]]
+
+
+def compose(
+    docstring: Docstring,
+    style: DocstringStyle = DocstringStyle.AUTO,
+    rendering_style: RenderingStyle = RenderingStyle.COMPACT,
+    indent: str = "    ",
+) -> str:
+    """Render a docstring object back to Google style text.
+
+    ``rendering_style`` is accepted for compatibility, the output is always compact.
+    """
+    if style not in (DocstringStyle.AUTO, DocstringStyle.GOOGLE):
+        raise ParseError("unsupported docstring style: {}".format(style.name))
+    parts = []
+    if docstring.short_description:
+        parts.append(docstring.short_description)
+    if docstring.blank_after_short_description:
+        parts.append("")
+    if docstring.long_description:
+        parts.append(docstring.long_description)
+    if docstring.blank_after_long_description:
+        parts.append("")
+
+    def section(title: str, entries: List[str]) -> None:
+        if parts and parts[-1] != "":
+            parts.append("")
+        parts.append(title + ":")
+        parts.extend(entries)
+
+    if docstring.deprecation is not None:
+        deprecation = docstring.deprecation
+        text = " ".join(
+            part for part in (deprecation.version, deprecation.description) if part
+        )
+        desc = _indented(text, indent)
+        section("Deprecated", [indent + desc[0]] + desc[1:])
+    if docstring.params:
+        entries = []
+        for param in docstring.params:
+            head = param.arg_name
+            if param.type_name:
+                optional = _OPTIONAL_SUFFIX if param.is_optional else ""
+                head = "{} ({}{})".format(head, param.type_name, optional)
+            desc = _indented(param.description, indent * 2)
+            entries.append("{}{}: {}".format(indent, head, desc[0]).rstrip())
+            entries.extend(desc[1:])
+        section("Args", entries)
+    for returns in docstring.many_returns:
+        desc = _indented(returns.description, indent)
+        first = desc[0]
+        if returns.type_name:
+            first = "{}: {}".format(returns.type_name, first).rstrip()
+        section("Returns", [indent + first] + desc[1:])
+    if docstring.raises:
+        entries = []
+        for raises in docstring.raises:
+            desc = _indented(raises.description, indent * 2)
+            entries.append("{}{}: {}".format(indent, raises.type_name, desc[0]).rstrip())
+            entries.extend(desc[1:])
+        section("Raises", entries)
+    for meta in docstring.meta:
+        title = _META_TITLES.get(meta.args[0]) if meta.args else None
+        if title is not None and type(meta) is DocstringMeta:
+            lines = (meta.description or "").splitlines()
+            section(title, [indent + line if line else "" for line in lines])
+    return "\n".join(parts).strip("\n")
@@ -0,0 +1,158 @@
+from docstring_parser.compat import (
+    DocstringParam,
+    DocstringStyle,
+    ParseError,
+    compose,
+    parse,
+)
+
+import pytest
+
+DOCSTRING = """
+    Short description.
+
+    Longer description
+    over two lines.
+
+    Args:
+        arg1 (int): Description of arg1
+        arg2 (str, optional): Description of arg2. Defaults to "x".
+    Returns:
+        bool: True on success.
+    Raises:
+        ValueError: If arg1 is negative.
+    """
+
+
+def test_parse():
+    doc = parse(DOCSTRING)
+    assert doc.style == DocstringStyle.GOOGLE
+    assert doc.short_description == "Short description."
+    assert doc.long_description == "Longer description\nover two lines."
+    assert doc.blank_after_short_description
+    assert doc.blank_after_long_description
+    assert [param.arg_name for param in doc.params] == ["arg1", "arg2"]
+    assert isinstance(doc.params[0], DocstringParam)
+    assert doc.params[0].type_name == "int"
+    assert doc.params[0].is_optional is False
+    assert doc.params[0].default is None
+    assert doc.params[1].type_name == "str"
+    assert doc.params[1].is_optional is True
+    assert doc.params[1].default == '"x"'
+    assert doc.returns.type_name == "bool"
+    assert doc.returns.description == "True on success."
+    assert [(r.type_name, r.description) for r in doc.raises] == [
+        ("ValueError", "If arg1 is negative.")
+    ]
+    assert len(doc.meta) == 4
+    assert doc.deprecation is None
+
+
+def test_parse_empty():
+    doc = parse("")
+    assert doc.short_description is None
+    assert doc.meta == []
+
+
+def test_unsupported_style():
+    with pytest.raises(ParseError):
+        parse(DOCSTRING, style=DocstringStyle.NUMPYDOC)
+
+
+def test_compose_round_trip():
+    text = compose(parse(DOCSTRING))
+    assert text == (
+        "Short description.\n"
+        "\n"
+        "Longer description\n"
+        "over two lines.\n"
+        "\n"
+        "Args:\n"
+        "    arg1 (int): Description of arg1\n"
+        '    arg2 (str, optional): Description of arg2. Defaults to "x".\n'
+        "\n"
+        "Returns:\n"
+        "    bool: True on success.\n"
+        "\n"
+        "Raises:\n"
+        "    ValueError: If arg1 is negative."
+    )
+    assert compose(parse(text)) == text
+
+    doc = parse(
+        """Short description.
+
+    Args:
+        x: The x.
+    Note:
+        Mind the x.
+    Warning:
+        It may fail.
+    Examples:
+        >>> f(1)
+        2
+    """
+    )
+    assert [meta.args for meta in doc.meta[1:]] == [["note"], ["warning"], ["examples"]]
+    text = compose(doc)
+    assert text == (
+        "Short description.\n"
+        "\n"
+        "Args:\n"
+        "    x: The x.\n"
+        "\n"
+        "Note:\n"
+        "    Mind the x.\n"
+        "\n"
+        "Warning:\n"
+        "    It may fail.\n"
+        "\n"
+        "Examples:\n"
+        "    >>> f(1)\n"
+        "    2"
+    )
+    assert compose(parse(text)) == text
+
+
+def test_deprecation():
+    doc = parse(
+        """Short description.
+
+    .. deprecated:: 2.5
+       Use other instead.
+    """
+    )
+    assert doc.deprecation.version == "2.5"
+    assert doc.deprecation.description == "Use other instead."
+
+    doc = parse(
+        """Short description.
+
+    Deprecated:
+        2.5 Use other instead.
+    Args:
+        x: The x.
+    """
+    )
+    assert doc.deprecation.args == ["deprecation"]
+    assert doc.deprecation.version == "2.5"
+    assert doc.deprecation.description == "Use other instead."
+    assert [param.arg_name for param in doc.params] == ["x"]
+    assert compose(doc).endswith(
+        "Deprecated:\n    2.5 Use other instead.\n\nArgs:\n    x: The x."
+    )
+
+
+def test_text_after_sections():
+    doc = parse(
+        """Short description.
+
+    Args:
+        x: The x.
+
+    More about it.
+    """
+    )
+    assert doc.short_description == "Short description."
+    assert doc.long_description == "More about it."
+    assert [param.arg_name for param in doc.params] == ["x"]
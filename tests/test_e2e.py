@@ -1,10 +1,16 @@
-import inspect
+import json
 
-from docstring_parser import parse
+import pytest
 
-
-def prepare_doc(docstring: str) -> str:
-    return "\n" + inspect.cleandoc(docstring)
+from docstring_parser import (
+    SCHEMA_VERSION,
+    DocstringParagraphType,
+    DocstringParseError,
+    InvalidValueError,
+    PyDocstring,
+    json_schema,
+    parse,
+)
 
 
 def test_parse_args():
@@ -13,8 +19,474 @@ def test_parse_args():
         arg1 (int): Description of arg1
         arg2 (str, optional): Description of arg2
     """
-    docstring = prepare_doc(docstring)
     parsed_doc = parse(docstring)
-    print(parsed_doc)
-    # breakpoint()
-    raise
+    assert len(parsed_doc) == 1
+    args = parsed_doc[0]
+    assert args.type == DocstringParagraphType.Args
+    assert [(arg.name, arg.type, arg.description) for arg in args.args] == [
+        ("arg1", "int", "Description of arg1"),
+        ("arg2", "str, optional", "Description of arg2"),
+    ]
+
+
+def test_parse_error_fields():
+    docstring = """
+    Args:
+        arg1 (int) Description of arg1
+    """
+    with pytest.raises(InvalidValueError) as exc_info:
+        parse(docstring)
+    err = exc_info.value
+    assert isinstance(err, DocstringParseError)
+    assert isinstance(err, ValueError)
+    assert err.lineno == 3
+    assert err.col == 19
+    assert err.pos == docstring.index("Description")
+    assert err.message == "expected ':', found 'D'"
+    assert err.code == "E003"
+    assert err.found == "'D'"
+    assert err.context == ["argument `arg1`", "section `Args`"]
+    assert err.expected == [":"]
+    assert err.docstring == docstring
+    assert "^" in str(err)
+
+
+def test_ast_properties():
+    docstring = """
+    Summary line.
+    Args:
+        arg1 (int): Description of arg1
+            more about arg1
+        arg2: Description of arg2
+    Returns:
+        Description of return value
+    """
+    parsed_doc = parse(docstring)
+    assert len(parsed_doc) == 3
+    args = parsed_doc[1]
+    assert [arg.name for arg in args.args] == ["arg1", "arg2"]
+    assert args.args[0].type == "int"
+    assert args.args[0].description == "Description of arg1\nmore about arg1"
+    assert args.args[0].lines == ["Description of arg1", "more about arg1"]
+    assert args.args[1].type is None
+    returns = parsed_doc[-1]
+    assert returns.args is None
+    assert returns.lines == ["Description of return value"]
+    assert returns.description == "Description of return value"
+    assert [p.lines for p in parsed_doc[1:]] == [[], returns.lines]
+    assert len(parsed_doc[::2]) == 2
+    assert [p.lines for p in parsed_doc] == [p.lines for p in parsed_doc[:]]
+    with pytest.raises(IndexError):
+        parsed_doc[3]
+    with pytest.raises(IndexError):
+        parsed_doc[-4]
+
+
+def test_section_queries():
+    docstring = """
+    Summary line.
+
+    Args:
+        arg1 (int): Description of arg1
+        arg2: Description of arg2
+    Returns:
+        Description of return value
+    Raises:
+        ValueError: If arg1 is negative.
+    """
+    parsed_doc = parse(docstring)
+    assert parsed_doc.summary == "Summary line."
+    assert [arg.name for arg in parsed_doc.params] == ["arg1", "arg2"]
+    assert parsed_doc.param("arg1").type == "int"
+    assert parsed_doc.param("missing") is None
+    assert parsed_doc.returns == ["Description of return value"]
+    assert [exc.name for exc in parsed_doc.raises] == ["ValueError"]
+    assert parsed_doc.examples is None
+    returns = parsed_doc.sections_of(DocstringParagraphType.Returns)
+    assert [section.lines for section in returns] == [parsed_doc.returns]
+
+
+def test_json_round_trip():
+    docstring = """
+    Summary line.
+
+    Args:
+        arg1 (int): Description of arg1
+    """
+    parsed_doc = parse(docstring)
+    data = parsed_doc.to_dict()
+    assert data["schema_version"] == SCHEMA_VERSION
+    args = data["sections"][2]["paragraph"]
+    assert args["kind"] == "args"
+    assert args["value"][0]["name"] == "arg1"
+    assert args["value"][0]["type"] == "int"
+    assert json.loads(parsed_doc.to_json(indent=2)) == data
+    restored = PyDocstring.from_json(parsed_doc.to_json())
+    assert restored.to_dict() == data
+    assert restored.param("arg1").type == "int"
+    with pytest.raises(ValueError):
+        PyDocstring.from_json('{"schema_version": 0, "sections": []}')
+
+
+def test_json_matches_schema():
+    jsonschema = pytest.importorskip("jsonschema")
+    docstring = """
+    Summary line.
+
+    Args:
+        arg1 (int): Description of arg1
+    Returns:
+        Description of return value
+    Raises:
+        ValueError: If arg1 is negative.
+    """
+    jsonschema.Draft202012Validator(json_schema()).validate(parse(docstring).to_dict())
+
+
+def test_parse_qualified_raises():
+    docstring = """
+    Raises:
+        requests.HTTPError: If the request fails.
+    """
+    assert [exc.name for exc in parse(docstring).raises] == ["requests.HTTPError"]
+    assert DocstringParagraphType.__name__ == "DocstringParagraphType"
+
+
+def test_parse_options():
+    docstring = """
+        Summary.
+
+        Arguments:
+            x (int): The x.
+        Notes:
+            Some note.
+    """
+    parsed = parse(
+        docstring,
+        cleandoc=True,
+        sections=["notes"],
+        aliases={"Arguments": "args"},
+    )
+    assert parsed.summary == "Summary."
+    assert parsed.param("x").type == "int"
+    assert [p.description for p in parsed.sections_of(DocstringParagraphType.Note)] == [
+        "Some note."
+    ]
+
+    with pytest.raises(InvalidValueError) as exc_info:
+        parse("Summary.", max_input_size=4)
+    assert exc_info.value.code == "E004"
+
+    malformed = "\nArgs:\n    arg1 Description.\n    arg2: Fine.\n"
+    assert parse(malformed, strict=False).param("arg2") is not None
+
+    with pytest.raises(ValueError):
+        parse("Summary.", sections=["bogus"])
+    with pytest.raises(ValueError):
+        parse("Summary.", style="numpy")
+
+
+def test_parse_raw_docstring():
+    def func(x, y):
+        """Do something.
+
+        Args:
+            x (int): The x.
+            y The y.
+        """
+
+    with pytest.raises(InvalidValueError) as exc_info:
+        parse(func.__doc__)
+    err = exc_info.value
+    assert err.lineno == 5
+    assert err.pos == func.__doc__.index("The y")
+
+    def func(x):
+        """
+        Do something.
+
+        Args:
+            x (int): The x.
+        """
+
+    parsed = parse(func.__doc__)
+    assert parsed.summary == "Do something."
+    assert parsed.param("x").type == "int"
+    assert parsed.param("x").name_span.start == func.__doc__.index("x (int)")
+
+
+def test_mixed_indentation_warning():
+    from docstring_parser import parse_with_diagnostics
+
+    docstring = "Summary.\n\nArgs:\n    x: The x.\n\ty: The y.\n"
+    parsed, diagnostics = parse_with_diagnostics(docstring)
+    assert [arg.name for arg in parsed.params] == ["x", "y"]
+    assert [(d.severity, d.code) for d in diagnostics] == [("warning", "W001")]
+    assert diagnostics[0].span.lineno == 5
+    assert diagnostics[0].render().startswith("warning[W001]: ")
+    with pytest.raises(TypeError):
+        parse_with_diagnostics(docstring, strict=True)
+
+
+def test_parse_crlf():
+    docstring = "Summary.\r\n\r\n    Args:\r\n        x (int): The x.\r\n    "
+    parsed = parse(docstring)
+    assert parsed.summary == "Summary."
+    assert parsed.param("x").lines == ["The x."]
+    assert parsed.param("x").name_span.start == docstring.index("x (int)")
+    assert parsed.param("x").name_span.lineno == 4
+
+
+def test_doctests():
+    from docstring_parser import PyDoctest
+
+    docstring = """Add two numbers.
+
+    Examples:
+        Small numbers:
+
+        >>> add(1,
+        ...     2)
+        3
+        >>> add(10**100, 1)  # doctest: +SKIP
+    """
+    parsed = parse(docstring)
+    doctests = parsed.doctests
+    assert [d.source for d in doctests] == ["add(1,\n    2)", "add(10**100, 1)  # doctest: +SKIP"]
+    assert [d.want for d in doctests] == ["3", ""]
+    assert doctests[1].options == ["+SKIP"]
+    assert doctests[0].span.start == docstring.index(">>>")
+    examples = parsed.sections_of(DocstringParagraphType.Examples)[0].examples
+    assert examples[0] == "Small numbers:"
+    assert isinstance(examples[1], PyDoctest)
+    assert parsed[0].examples is None
+
+
+def test_code_blocks():
+    docstring = """Run the server.
+
+    Examples:
+        .. code-block:: python
+           :name: code-example1
+
+           server = Server()
+           server.run()
+
+        ```sh
+        serve --port 80
+        ```
+    """
+    blocks = parse(docstring).code_blocks
+    assert [(b.language, b.code) for b in blocks] == [
+        ("python", "server = Server()\nserver.run()"),
+        ("sh", "serve --port 80"),
+    ]
+    assert blocks[0].options == [("name", "code-example1")]
+    assert blocks[0].span.lineno == 4
+
+
+def test_directives():
+    docstring = """Open a file.
+
+    .. deprecated:: 2.5 Use open_path instead.
+
+    .. image:: diagram.png
+       :alt: The flow.
+    """
+    directives = parse(docstring).directives
+    assert [(d.name, d.version) for d in directives] == [
+        ("deprecated", "2.5"),
+        ("image", None),
+    ]
+    assert directives[0].content == "Use open_path instead."
+    assert directives[1].argument == "diagram.png"
+    assert directives[1].options == [("alt", "The flow.")]
+    assert directives[1].span.lineno == 5
+
+
+def test_version_changes():
+    docstring = """Open a file.
+
+    .. versionadded:: 1.0
+
+    Deprecated:
+        2.5 Use open_path instead.
+    """
+    parsed = parse(docstring)
+    changes = parsed.version_changes
+    assert [(c.kind, c.version) for c in changes] == [
+        ("added", "1.0"),
+        ("deprecated", "2.5"),
+    ]
+    assert parsed.deprecation.description == "Use open_path instead."
+    assert parsed.deprecation.span.lineno == 5
+    assert len(parsed.sections_of(DocstringParagraphType.Deprecated)) == 1
+
+
+def test_inlines():
+    docstring = """Add tensors.
+
+    Args:
+        x: See :func:`paddle.add` and `the guide <https://x.org>`_.
+    """
+    inlines = parse(docstring).sections_of(DocstringParagraphType.Args)[0].args[0].inlines
+    assert [(node.kind, node.text) for node in inlines] == [
+        ("text", "See "),
+        ("role", "paddle.add"),
+        ("text", " and "),
+        ("link", "the guide"),
+        ("text", "."),
+    ]
+    assert inlines[1].role == "func"
+    assert inlines[3].target == "https://x.org"
+
+
+def test_blocks():
+    docstring = """Reduce a tensor.
+
+    Example::
+
+        reduce(x)
+
+    Args:
+        mode: One of
+            1. sum, the total,
+            2. mean.
+    """
+    parsed = parse(docstring)
+    assert [b.kind for b in parsed.description_blocks] == [
+        "paragraph",
+        "paragraph",
+        "literal_block",
+    ]
+    assert parsed.description_blocks[2].text == "reduce(x)"
+    blocks = parsed.sections_of(DocstringParagraphType.Args)[0].args[0].blocks
+    assert [b.kind for b in blocks] == ["paragraph", "ordered_list"]
+    assert blocks[1].start == 1
+    assert [[i.text for i in item[0].inlines] for item in blocks[1].items] == [
+        ["sum, the total,"],
+        ["mean."],
+    ]
+
+
+def test_to_markdown():
+    docstring = """Add two tensors.
+
+    Args:
+        x (Tensor): The first one.
+    Notes:
+        Broadcasts.
+    """
+    parsed = parse(docstring, sections=["args", "notes"])
+    assert parsed.to_markdown() == (
+        "Add two tensors.\n\n"
+        "## Args\n\n"
+        "| Name | Type | Description |\n"
+        "| --- | --- | --- |\n"
+        "| `x` | `Tensor` | The first one. |\n\n"
+        "> **Note**\n>\n> Broadcasts.\n"
+    )
+    assert "### Args\n\n- **x** (`Tensor`): The first one.\n" in parsed.to_markdown(
+        heading_level=3, layout="list"
+    )
+    with pytest.raises(ValueError):
+        parsed.to_markdown(layout="grid")
+
+
+def test_to_html():
+    docstring = """Add <two> tensors, see :func:`paddle.add`.
+
+    Args:
+        x (Tensor): The first one.
+    """
+    parsed = parse(docstring)
+    html = parsed.to_html(id_prefix="add")
+    assert "<p>Add &lt;two&gt; tensors" in html
+    assert '<section id="add-args">' in html
+    assert '<dt id="add-params-x"><code class="name">x</code>' in html
+
+    def resolve(role, target):
+        return f"https://docs/{target}" if role == "func" else None
+
+    assert (
+        '<a class="reference" href="https://docs/paddle.add">'
+        in parsed.to_html(resolve=resolve)
+    )
+
+    def fail(role, target):
+        raise KeyError(target)
+
+    with pytest.raises(KeyError):
+        parsed.to_html(resolve=fail)
+
+
+def test_format_docstring_section_order():
+    from docstring_parser import format_docstring
+
+    docstring = """Summary.
+
+    Args:
+        x: The x.
+    Returns:
+        The y.
+    """
+    assert format_docstring(docstring, section_order=["returns", "args"]) == (
+        "Summary.\n\nReturns:\n    The y.\n\nArgs:\n    x: The x."
+    )
+    with pytest.raises(ValueError):
+        format_docstring(docstring, section_order=["bogus"])
+
+
+def test_format_docstring_parse_options():
+    from docstring_parser import format_docstring
+
+    docstring = "Summary.\n\nNotes:\n\tMind the x.\nArguments:\n\tx: The x.\n"
+    assert format_docstring(
+        docstring,
+        tab_size=8,
+        cleandoc=False,
+        sections=["args", "notes"],
+        aliases={"Arguments": "args"},
+    ) == "Summary.\n\nArgs:\n    x: The x.\n\nNotes:\n    Mind the x."
+    with pytest.raises(ValueError):
+        format_docstring(docstring, sections=["bogus"])
+
+
+def test_parse_cst():
+    from docstring_parser import CstNode, CstToken, parse_cst
+
+    docstring = "Summary.  \n\nArgs:\n    x  (int):  The x.\n"
+    cst = parse_cst(docstring)
+    assert str(cst) == docstring
+    assert cst.kind == "Docstring"
+    section = [c for c in cst.children if c.kind == "Section"][0]
+    arg_list = [c for c in section.children if c.kind == "ArgList"][0]
+    argument = [c for c in arg_list.children if isinstance(c, CstNode)][0]
+    type_token = [c for c in argument.children if c.kind == "Type"][0]
+    assert isinstance(type_token, CstToken)
+    assert type_token.text == "int"
+    assert docstring[type_token.offset :].startswith("int)")
+    assert cst.to_docstring().param("x").type == "int"
+
+    malformed = "Args:\n    x int: The x.\n"
+    with pytest.raises(ValueError):
+        parse_cst(malformed)
+    assert str(parse_cst(malformed, strict=False)) == malformed
+
+    cst = parse_cst("Notes:\n    Mind the x.\n", sections=["notes"])
+    section = cst.to_docstring()[0]
+    assert section.type == DocstringParagraphType.Note
+
+
+def test_format_docstring_error_positions():
+    from docstring_parser import format_docstring
+
+    docstring = """Summary.
+
+    Args:
+    \tx int: The x.
+    """
+    with pytest.raises(InvalidValueError) as exc_info:
+        format_docstring(docstring)
+    assert exc_info.value.pos == docstring.index("int")
+    assert exc_info.value.lineno == 4